@@ -0,0 +1,149 @@
+use crate::sprite::kart::KartStats;
+use std::fs;
+
+/*
+ * The karts that can be picked before a race are listed in a text file.
+ * Each line is `key = value`, blank lines and lines starting with # are
+ * ignored. Every kart starts with a name line and the lines after it
+ * belong to that kart:
+ *
+ *   name = <name of the kart>
+ *   sprite = <path to the sprite sheet>
+ *   acceleration = <how quickly the kart speeds up>
+ *   top_speed = <multiplies the fastest the kart can go on every surface>
+ *   handling = <fastest the kart can turn>
+ *   weight = <how hard the kart is to push around>
+ *
+ * stats need to be above 0, stats that are left out are the same as the
+ * default kart's
+ * */
+
+pub const CHARACTERS_PATH: &str = "assets/karts.txt";
+const DEFAULT_SPRITE: &str = "assets/images/kart1.png";
+
+pub struct Character {
+    pub name: String,
+    pub sprite: String, //Path to the sprite sheet
+    pub stats: KartStats,
+}
+
+impl Character {
+    fn new(name: &str) -> Self {
+        Self {
+            name: String::from(name),
+            sprite: String::from(DEFAULT_SPRITE),
+            stats: KartStats::default(),
+        }
+    }
+}
+
+fn parse_characters(src: &str) -> Result<Vec<Character>, String> {
+    let mut characters: Vec<Character> = vec![];
+
+    for (i, line) in src.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+
+        let (key, value) = line
+            .split_once('=')
+            .ok_or(format!("line {}: expected `key = value`", i + 1))?;
+        let (key, value) = (key.trim(), value.trim());
+
+        if key == "name" {
+            characters.push(Character::new(value));
+            continue;
+        }
+
+        let character = characters.last_mut().ok_or(format!(
+            "line {}: `{key}` needs to come after a name",
+            i + 1
+        ))?;
+        let number = || match value.parse::<f64>() {
+            Ok(n) if n.is_finite() && n > 0.0 => Ok(n),
+            Ok(_) => Err(format!("line {}: {key} needs to be above 0", i + 1)),
+            Err(e) => Err(format!("line {}: {value}: {e}", i + 1)),
+        };
+
+        match key {
+            "sprite" => character.sprite = String::from(value),
+            "acceleration" => character.stats.acceleration = number()?,
+            "top_speed" => character.stats.top_speed = number()?,
+            "handling" => character.stats.handling = number()?,
+            "weight" => character.stats.weight = number()?,
+            _ => return Err(format!("line {}: unknown kart property `{key}`", i + 1)),
+        }
+    }
+
+    if characters.is_empty() {
+        return Err(String::from("no karts"));
+    }
+
+    Ok(characters)
+}
+
+pub fn load_characters(path: &str) -> Result<Vec<Character>, String> {
+    match fs::read_to_string(path) {
+        Ok(src) => parse_characters(&src).map_err(|e| format!("{path}: {e}")),
+        Err(msg) => {
+            eprintln!("{msg}");
+            eprintln!("Cannot open file: {path}");
+            Err(String::from("Failed to open kart file"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn left_out_stats_are_the_defaults() {
+        let karts = parse_characters("name = Slow Crab\ntop_speed = 0.9\n").unwrap();
+        let defaults = KartStats::default();
+        assert_eq!(karts[0].sprite, DEFAULT_SPRITE);
+        assert_eq!(karts[0].stats.top_speed, 0.9);
+        assert_eq!(karts[0].stats.acceleration, defaults.acceleration);
+        assert_eq!(karts[0].stats.handling, defaults.handling);
+        assert_eq!(karts[0].stats.weight, defaults.weight);
+    }
+
+    #[test]
+    fn stats_belong_to_the_kart_above_them() {
+        let src = "name = Red Crab\nhandling = 0.4\n\n# next kart\nname = Blue Crab\n";
+        let karts = parse_characters(src).unwrap();
+        assert_eq!(karts[0].stats.handling, 0.4);
+        assert_eq!(karts[1].stats.handling, KartStats::default().handling);
+
+        let err = parse_characters("handling = 0.4\nname = Red Crab\n")
+            .err()
+            .unwrap();
+        assert!(err.starts_with("line 1:"), "{err}");
+    }
+
+    #[test]
+    fn stats_need_to_be_above_zero() {
+        for stat in [
+            "weight = 0",
+            "top_speed = -1",
+            "handling = NaN",
+            "acceleration = inf",
+        ] {
+            let err = parse_characters(&format!("name = Red Crab\n{stat}\n"))
+                .err()
+                .unwrap();
+            assert!(err.starts_with("line 2:"), "{stat}: {err}");
+        }
+    }
+
+    #[test]
+    fn a_file_needs_at_least_one_kart() {
+        assert!(parse_characters("# no karts yet\n").is_err());
+    }
+
+    #[test]
+    fn the_shipped_karts_load() {
+        assert!(!load_characters(CHARACTERS_PATH).unwrap().is_empty());
+    }
+}
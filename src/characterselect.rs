@@ -0,0 +1,249 @@
+use sdl2::{
+    keyboard::Keycode,
+    pixels::Color,
+    rect::{Point, Rect},
+    render::{Canvas, Texture, TextureCreator},
+    ttf::Font,
+    video::{Window, WindowContext},
+};
+
+use crate::{
+    character::Character,
+    events::Events,
+    menu::{Button, Text},
+    sprite::{kart::KartStats, load_assets::KART_SLOTS},
+    GameScreen,
+};
+
+const PREVIEW_SIZE: u32 = 128;
+//Frame of the sprite sheet that is shown, the kart turned to the side a little
+const PREVIEW_FRAME: i32 = 1;
+//Number of segments in the bar for each stat
+const STAT_BAR_LENGTH: i32 = 10;
+const STAT_NAMES: [&str; 4] = ["Accel", "Speed", "Handling", "Weight"];
+
+//Stats in the same order as STAT_NAMES
+fn stat_values(stats: &KartStats) -> [f64; 4] {
+    [
+        stats.acceleration,
+        stats.top_speed,
+        stats.handling,
+        stats.weight,
+    ]
+}
+
+//Lets each player pick the kart that they race with
+pub struct CharacterSelectScreen {
+    pub selected: [usize; 2], //Kart picked by each player
+    mode: GameScreen,         //The kind of race that the karts are being picked for
+    prev_buttons: [Button; 2],
+    next_buttons: [Button; 2],
+    continue_button: Button,
+    back_button: Button,
+}
+
+impl CharacterSelectScreen {
+    pub fn init(race_mode: GameScreen, characters: &[Character]) -> Self {
+        let mut character_select = Self {
+            selected: [0, 1 % characters.len()],
+            mode: race_mode,
+            prev_buttons: [Button::new(0, -72, "<"), Button::new(0, -72, "<")],
+            next_buttons: [Button::new(0, -72, ">"), Button::new(0, -72, ">")],
+            continue_button: Button::new(0, 192, "Continue"),
+            back_button: Button::new(0, 240, "Back"),
+        };
+
+        for player in 0..2 {
+            let x = character_select.column_x(player);
+            character_select.prev_buttons[player].x = x - 176;
+            character_select.next_buttons[player].x = x + 176;
+        }
+
+        character_select
+    }
+
+    fn player_count(&self) -> usize {
+        if self.mode == GameScreen::TwoPlayer {
+            2
+        } else {
+            1
+        }
+    }
+
+    //Center of each player's column relative to the center of the screen
+    fn column_x(&self, player: usize) -> i32 {
+        match (self.player_count(), player) {
+            (1, _) => 0,
+            (_, 0) => -240,
+            _ => 240,
+        }
+    }
+
+    //The karts in the race, the players' karts come first followed by
+    //the ones for the computer karts (karts nobody picked come first)
+    pub fn racers<'a>(&self, characters: &'a [Character]) -> Vec<&'a Character> {
+        let picked = &self.selected[..self.player_count()];
+        let others = (0..characters.len())
+            .filter(|i| !picked.contains(i))
+            .chain(0..characters.len())
+            .cycle();
+
+        picked
+            .iter()
+            .copied()
+            .chain(others)
+            .take(KART_SLOTS.len())
+            .map(|i| &characters[i])
+            .collect()
+    }
+
+    pub fn press_buttons(
+        &mut self,
+        events: &mut Events,
+        canvas_dimensions: (u32, u32),
+        characters: &[Character],
+    ) -> Option<GameScreen> {
+        let count = characters.len();
+        //Player 1 uses the arrow keys, player 2 uses A and D
+        let keys = [(Keycode::Left, Keycode::Right), (Keycode::A, Keycode::D)];
+
+        for (player, (prev_key, next_key)) in keys.into_iter().enumerate().take(self.player_count())
+        {
+            if self.prev_buttons[player].clicked(events, canvas_dimensions)
+                || events.key_is_pressed_once(prev_key)
+            {
+                self.selected[player] = (self.selected[player] + count - 1) % count;
+            } else if self.next_buttons[player].clicked(events, canvas_dimensions)
+                || events.key_is_pressed_once(next_key)
+            {
+                self.selected[player] = (self.selected[player] + 1) % count;
+            }
+        }
+
+        if self.continue_button.clicked(events, canvas_dimensions)
+            || events.key_is_pressed_once(Keycode::Return)
+        {
+            return Some(GameScreen::TrackSelect);
+        } else if self.back_button.clicked(events, canvas_dimensions)
+            || events.key_is_pressed_once(Keycode::Escape)
+        {
+            return Some(GameScreen::MainMenu);
+        }
+
+        None
+    }
+
+    //Draws a bar showing how a kart's stat compares to the other karts
+    fn display_stat_bar(
+        canvas: &mut Canvas<Window>,
+        value: f64,
+        (min, max): (f64, f64),
+        x: i32,
+        y: i32,
+    ) -> Result<(), String> {
+        let filled = if max > min {
+            2 + ((value - min) / (max - min) * (STAT_BAR_LENGTH - 2) as f64).round() as i32
+        } else {
+            STAT_BAR_LENGTH / 2
+        };
+
+        for i in 0..STAT_BAR_LENGTH {
+            let color = if i < filled {
+                Color::RGB(255, 128, 0)
+            } else {
+                Color::RGBA(0, 0, 0, 128)
+            };
+            canvas.set_draw_color(color);
+            canvas
+                .fill_rect(Rect::new(x + i * 16, y + 8, 12, 16))
+                .map_err(|e| e.to_string())?;
+        }
+
+        Ok(())
+    }
+
+    fn display_player(
+        &self,
+        canvas: &mut Canvas<Window>,
+        texture_creator: &TextureCreator<WindowContext>,
+        font: &Font,
+        characters: &[Character],
+        previews: &[Texture],
+        player: usize,
+    ) -> Result<(), String> {
+        let (canv_w, canv_h) = canvas.output_size()?;
+        let x = canv_w as i32 / 2 + self.column_x(player);
+        let center_y = canv_h as i32 / 2;
+        let character = &characters[self.selected[player]];
+
+        if self.player_count() > 1 {
+            let player_text = Text::new(
+                format!("Player {}", player + 1).as_str(),
+                x,
+                center_y - 168,
+                Color::WHITE,
+                16,
+            );
+            player_text.display_center(canvas, texture_creator, font)?;
+        }
+
+        let preview_rect =
+            Rect::from_center(Point::new(x, center_y - 72), PREVIEW_SIZE, PREVIEW_SIZE);
+        canvas
+            .copy(
+                &previews[self.selected[player]],
+                Rect::new(PREVIEW_FRAME * 32, 0, 32, 32),
+                preview_rect,
+            )
+            .map_err(|e| e.to_string())?;
+
+        let name_text = Text::new(character.name.as_str(), x, center_y + 8, Color::WHITE, 24);
+        name_text.display_center(canvas, texture_creator, font)?;
+
+        for (i, label) in STAT_NAMES.iter().enumerate() {
+            let y = center_y + 48 + i as i32 * 26;
+            let label_text = Text::new(label, x - 160, y, Color::WHITE, 16);
+            label_text.display_left_justify(canvas, texture_creator, font)?;
+
+            let values = characters
+                .iter()
+                .map(|character| stat_values(&character.stats)[i]);
+            let range = (
+                values.clone().fold(f64::INFINITY, f64::min),
+                values.fold(f64::NEG_INFINITY, f64::max),
+            );
+            let value = stat_values(&character.stats)[i];
+            Self::display_stat_bar(canvas, value, range, x - 8, y)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn display(
+        &self,
+        canvas: &mut Canvas<Window>,
+        texture_creator: &TextureCreator<WindowContext>,
+        events: &Events,
+        font: &Font,
+        characters: &[Character],
+        previews: &[Texture],
+    ) -> Result<(), String> {
+        let (canv_w, _) = canvas.output_size()?;
+
+        let title_text = Text::new("Select Kart", canv_w as i32 / 2, 32, Color::WHITE, 32);
+        title_text.display_center(canvas, texture_creator, font)?;
+
+        for player in 0..self.player_count() {
+            self.display_player(canvas, texture_creator, font, characters, previews, player)?;
+            self.prev_buttons[player].display(canvas, texture_creator, events, font)?;
+            self.next_buttons[player].display(canvas, texture_creator, events, font)?;
+        }
+
+        self.continue_button
+            .display(canvas, texture_creator, events, font)?;
+        self.back_button
+            .display(canvas, texture_creator, events, font)?;
+
+        Ok(())
+    }
+}
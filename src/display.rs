@@ -1,6 +1,7 @@
 use crate::level::Camera;
 use crate::menu::Text;
-use crate::sprite::kart::{Kart, PowerupType};
+use crate::race;
+use crate::sprite::kart::Kart;
 use crate::sprite::{Sprite, SpriteType};
 use sdl2::pixels::Color;
 use sdl2::rect::{Point, Rect};
@@ -21,7 +22,7 @@ pub fn display_sprites(
     canvas_dimensions: (u32, u32),
     canvas_origin: (i32, i32),
     buff_dimensions: (usize, usize),
-    sprite_assets: &HashMap<SpriteType, Texture>,
+    sprite_assets: &mut HashMap<SpriteType, Texture>,
 ) -> Result<(), String> {
     //Draw the sprites
     for spr in sprites {
@@ -50,6 +51,17 @@ pub fn calculate_texture_rect(canvas_dimensions: (u32, u32), buff_w: usize, buff
     }
 }
 
+//Formats a time in seconds as minutes:seconds.hundredths
+pub fn format_time(time: f64) -> String {
+    let hundredths = (time * 100.0).round() as u64;
+    format!(
+        "{}:{:02}.{:02}",
+        hundredths / 6000,
+        hundredths / 100 % 60,
+        hundredths % 100
+    )
+}
+
 //Draws text for the player kart
 pub fn display_player_info(
     canvas: &mut Canvas<Window>,
@@ -92,19 +104,88 @@ pub fn display_player_info(
     );
     pos_text.display_left_justify(canvas, texture_creator, font)?;
 
+    //Display the kart's place in the race
+    if kart.position > 0 {
+        let (canv_w, _) = canvas.output_size()?;
+        let place_text = Text::new(
+            race::ordinal(kart.position).as_str(),
+            canv_w as i32 - 16,
+            y + 24,
+            Color::WHITE,
+            32,
+        );
+        place_text.display_right_justify(canvas, texture_creator, font)?;
+    }
+
+    //Flash a warning if the kart is going the wrong way
+    if kart.wrong_way() && (kart.wrong_way_timer * 4.0).floor() as i32 % 2 == 0 {
+        let (canv_w, canv_h) = canvas.output_size()?;
+        let wrong_way_text = Text::new(
+            "WRONG WAY",
+            canv_w as i32 / 2,
+            y + canv_h as i32 / 4 - 32,
+            Color::RED,
+            32,
+        );
+        wrong_way_text.display_center(canvas, texture_creator, font)?;
+    }
+
+    Ok(())
+}
+
+//Draws the current, last and best lap times of the kart and
+//how far ahead or behind its best lap it was at the last checkpoint
+pub fn display_lap_times(
+    canvas: &mut Canvas<Window>,
+    texture_creator: &TextureCreator<WindowContext>,
+    font: &Font,
+    kart: &Kart,
+    time: f64,
+    y: i32,
+) -> Result<(), String> {
+    let timer = &kart.lap_timer;
+    let format_lap = |lap: Option<f64>| lap.map_or(String::from("-:--.--"), format_time);
+    let lines = [
+        format!("lap:  {}", format_time(timer.current_lap_time(time))),
+        format!("last: {}", format_lap(timer.last_lap())),
+        format!("best: {}", format_lap(timer.best_lap())),
+    ];
+
+    for (i, line) in lines.iter().enumerate() {
+        let lap_text = Text::new(line, 16, y + 104 + i as i32 * 24, Color::WHITE, 16);
+        lap_text.display_left_justify(canvas, texture_creator, font)?;
+    }
+
+    if let Some(delta) = timer.split_delta(time) {
+        let (canv_w, _) = canvas.output_size()?;
+        let (sign, color) = if delta < 0.0 {
+            ("-", Color::GREEN)
+        } else {
+            ("+", Color::RED)
+        };
+        let delta_text = Text::new(
+            format!("{sign}{:.2}", delta.abs()).as_str(),
+            canv_w as i32 - 16,
+            y + 96,
+            color,
+            24,
+        );
+        delta_text.display_right_justify(canvas, texture_creator, font)?;
+    }
+
     Ok(())
 }
 
 pub fn display_powerup_icons(
     canvas: &mut Canvas<Window>,
-    icons: &HashMap<PowerupType, Texture>,
+    icons: &[Texture],
     icon_sz: u32,
     kart: &Kart,
     x: i32,
     y: i32,
 ) -> Result<(), String> {
     for i in 0..(kart.powerup_amt as i32) {
-        if let Some(tex) = icons.get(&kart.powerup) {
+        if let Some(tex) = kart.powerup.and_then(|item| icons.get(item.0)) {
             let offset =
                 i * icon_sz as i32 / 4 * 3 - icon_sz as i32 * kart.powerup_amt as i32 / 8 * 3;
             let rect = Rect::new(x + offset, y, icon_sz, icon_sz);
@@ -22,6 +22,7 @@ pub struct Events {
     event_pump: EventPump,
     click_state: ClickState,
     pub mouse_state: MouseState,
+    pub text_input: String, //Text that was typed since the last update
     pub can_quit: bool,
 }
 
@@ -35,6 +36,7 @@ impl Events {
             event_pump: new_event_pump,
             click_state: ClickState::Unheld,
             mouse_state: new_mouse_state,
+            text_input: String::new(),
             can_quit: false,
         })
     }
@@ -50,6 +52,8 @@ impl Events {
             self.click_state = ClickState::Unheld;
         }
 
+        self.text_input.clear();
+
         for event in self.event_pump.poll_iter() {
             match event {
                 Event::Quit { .. } => self.can_quit = true,
@@ -67,6 +71,8 @@ impl Events {
                 } => {
                     self.pressed_keys.remove(&k);
                 }
+                //Text was typed
+                Event::TextInput { text, .. } => self.text_input.push_str(&text),
                 _ => {}
             }
         }
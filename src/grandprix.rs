@@ -0,0 +1,104 @@
+use crate::race::RaceRules;
+use crate::sprite::ai_kart::Difficulty;
+
+//Points for finishing 1st, 2nd, 3rd and 4th in a race
+const POINTS: [u32; 4] = [10, 6, 3, 1];
+
+//A cup of single player races on every track, the racers get points
+//based on where they finish and whoever has the most points at the end wins
+pub struct GrandPrix {
+    pub rules: RaceRules,
+    pub difficulty: Difficulty,
+    //The player followed by the computer karts, in the same order
+    //as the positions passed to `award_points`
+    pub racer_names: Vec<String>,
+    tracks: Vec<usize>, //Index of the track for each round
+    round: usize,
+    points: [u32; 4],
+    last_points: [u32; 4], //Points each racer got in the last race
+    last_positions: [usize; 4],
+}
+
+impl GrandPrix {
+    //Starts a cup on the first track, going through every track in order
+    pub fn new(
+        first_track: usize,
+        track_count: usize,
+        rules: RaceRules,
+        difficulty: Difficulty,
+        racer_names: &[String],
+    ) -> Self {
+        Self {
+            rules,
+            difficulty,
+            racer_names: racer_names.to_vec(),
+            tracks: (0..track_count)
+                .map(|i| (first_track + i) % track_count)
+                .collect(),
+            round: 0,
+            points: [0; 4],
+            last_points: [0; 4],
+            last_positions: [0; 4],
+        }
+    }
+
+    //Rules for the current round, every round has its own seed
+    pub fn round_rules(&self) -> RaceRules {
+        RaceRules {
+            seed: self.rules.seed.wrapping_add(self.round as u64),
+            ..self.rules
+        }
+    }
+
+    pub fn current_track(&self) -> usize {
+        self.tracks[self.round.min(self.tracks.len() - 1)]
+    }
+
+    //Number of the race that was just finished (starting at 1)
+    pub fn round(&self) -> usize {
+        self.round
+    }
+
+    pub fn round_count(&self) -> usize {
+        self.tracks.len()
+    }
+
+    //Returns true once every round has been raced
+    pub fn finished(&self) -> bool {
+        self.round >= self.tracks.len()
+    }
+
+    //Gives each racer points for their position (1st = 1) in the race
+    //that just finished and moves on to the next round
+    pub fn award_points(&mut self, positions: &[usize]) {
+        for (racer, &position) in positions.iter().enumerate().take(POINTS.len()) {
+            let points = position
+                .checked_sub(1)
+                .and_then(|i| POINTS.get(i))
+                .copied()
+                .unwrap_or(0);
+            self.points[racer] += points;
+            self.last_points[racer] = points;
+            self.last_positions[racer] = position;
+        }
+
+        self.round += 1;
+    }
+
+    //Returns the racers ordered from most to least points as
+    //(index into racer_names, total points, points from the last race),
+    //ties go to whoever did better in the last race
+    pub fn standings(&self) -> Vec<(usize, u32, u32)> {
+        let mut racers: Vec<usize> = (0..self.points.len()).collect();
+        racers.sort_by(|&a, &b| {
+            self.points[b]
+                .cmp(&self.points[a])
+                .then(self.last_positions[a].cmp(&self.last_positions[b]))
+        });
+
+        racers
+            .into_iter()
+            .map(|racer| (racer, self.points[racer], self.last_points[racer]))
+            .collect()
+    }
+}
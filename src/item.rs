@@ -0,0 +1,226 @@
+use crate::level::Level;
+use crate::race::RaceRules;
+use crate::sprite::{
+    dist_between, enemy::Enemy, kart::Kart, powerup::Powerup, projectile::Projectile, xz_diff_norm,
+    Sprite,
+};
+use rand::{rngs::StdRng, SeedableRng};
+
+pub mod registry;
+
+use registry::{ItemId, ItemOdds, ItemUse};
+
+const POWERUP_SPAWN_DIST: f64 = 0.3;
+const PICKUP_DIST: f64 = 0.2;
+//How close an item needs to get to a kart or enemy to hit it
+const KART_HIT_DIST: f64 = 0.1;
+const ENEMY_HIT_DIST: f64 = 0.2;
+
+//Knocks out the kart if the sprite is touching it, returns true if the kart was hit
+pub fn knockout_kart(kart: &mut Kart, spr: &Sprite, dist: f64, time: f64) -> bool {
+    if dist_between(spr, &kart.sprite) < dist {
+        kart.hit(time);
+        return true;
+    }
+
+    false
+}
+
+//Stuns the first enemy that the sprite is touching
+fn stun_enemy(enemies: &mut [Enemy], spr: &Sprite, time: f64) -> bool {
+    match enemies
+        .iter_mut()
+        .find(|enemy| !enemy.stunned() && dist_between(&enemy.sprite, spr) < ENEMY_HIT_DIST)
+    {
+        Some(enemy) => {
+            enemy.stun(time);
+            true
+        }
+        None => false,
+    }
+}
+
+//Gives the kart an item depending on where it is in the race
+fn pickup_powerup(
+    powerup: &mut Powerup,
+    kart: &mut Kart,
+    odds: ItemOdds,
+    rng: &mut StdRng,
+    racers: usize,
+) {
+    if dist_between(&powerup.sprite, &kart.sprite) < PICKUP_DIST
+        && powerup.can_pickup()
+        && kart.powerup_amt == 0
+    {
+        powerup.sprite.width = 0.0;
+        powerup.sprite.height = 0.0;
+        if let Some((item, amount)) = odds.roll(rng, kart.position, racers) {
+            kart.pickup_powerup(item, amount);
+        }
+    }
+}
+
+//The powerup boxes on the track and the items that have been used,
+//the karts in the race are passed in and referred to by their index
+pub struct Items {
+    powerups: Vec<Powerup>,
+    projectiles: Vec<Projectile>,
+    odds: ItemOdds,
+    rng: StdRng, //Seeded from the race rules so races can be played again
+}
+
+impl Items {
+    pub fn new(level: &Level, rules: RaceRules) -> Self {
+        Self {
+            powerups: if rules.items != ItemOdds::Off {
+                level.create_powerups()
+            } else {
+                vec![]
+            },
+            projectiles: vec![],
+            odds: rules.items,
+            rng: StdRng::seed_from_u64(rules.seed),
+        }
+    }
+
+    //Homing items chase the kart one place ahead, or the kart
+    //in second place if they are used by the leader
+    fn homing_target(karts: &[&mut Kart], user: usize) -> Option<usize> {
+        let position = karts[user].position;
+        let target_position = if position > 1 { position - 1 } else { 2 };
+
+        karts
+            .iter()
+            .position(|kart| kart.position == target_position)
+            .filter(|&target| target != user)
+            .or((0..karts.len()).find(|&target| target != user))
+    }
+
+    fn launch(&mut self, karts: &mut [&mut Kart], user: usize, item: ItemId) {
+        let kart = &karts[user];
+        let (x, z) = (kart.sprite.trans_x, kart.sprite.trans_z);
+        let forward = (kart.sprite.rotation.sin(), kart.sprite.rotation.cos());
+        let spawn =
+            |(dx, dz): (f64, f64)| (x + POWERUP_SPAWN_DIST * dx, z + POWERUP_SPAWN_DIST * dz);
+
+        let projectile = match item.kind().usage {
+            ItemUse::Drop => {
+                let behind = (-forward.0, -forward.1);
+                Projectile::new(spawn(behind), item, user, (0.0, 0.0), None)
+            }
+            ItemUse::Homing => {
+                //Fly straight ahead if there is nobody to chase
+                let target = Self::homing_target(karts, user);
+                let direction = target
+                    .map(|target| xz_diff_norm(&karts[target].sprite, &kart.sprite))
+                    .unwrap_or(forward);
+                Projectile::new(spawn(direction), item, user, direction, target)
+            }
+            ItemUse::Thrown { .. } => Projectile::new(spawn(forward), item, user, forward, None),
+            ItemUse::Shield => {
+                //Using a shield replaces the one the kart already has
+                self.projectiles.retain(|projectile| {
+                    projectile.owner != user || projectile.item.kind().usage != ItemUse::Shield
+                });
+                karts[user].shielded = true;
+                Projectile::new((x, z), item, user, (0.0, 0.0), Some(user))
+            }
+            ItemUse::Boost(_) | ItemUse::Strike => return,
+        };
+
+        self.projectiles.push(projectile);
+    }
+
+    pub fn use_powerup(&mut self, karts: &mut [&mut Kart], user: usize) {
+        let Some(item) = karts[user].use_powerup() else {
+            return;
+        };
+        let kind = item.kind();
+
+        match kind.usage {
+            ItemUse::Boost(speed) => karts[user].speed += speed,
+            ItemUse::Strike => {
+                let position = karts[user].position;
+                for kart in karts.iter_mut() {
+                    if kart.position > 0 && kart.position < position {
+                        kart.hit(kind.knockout);
+                    }
+                }
+            }
+            _ => self.launch(karts, user, item),
+        }
+    }
+
+    fn update_powerups(&mut self, karts: &mut [&mut Kart], dt: f64) {
+        let racers = karts.len();
+        for powerup in &mut self.powerups {
+            for kart in karts.iter_mut() {
+                pickup_powerup(powerup, kart, self.odds, &mut self.rng, racers);
+            }
+            powerup.update(dt);
+        }
+    }
+
+    //Returns false once the projectile is used up
+    fn hit_targets(
+        projectile: &Projectile,
+        karts: &mut [&mut Kart],
+        enemies: &mut [Enemy],
+    ) -> bool {
+        let kind = projectile.item.kind();
+
+        //Shields last until they block a hit or wear off
+        if kind.usage == ItemUse::Shield {
+            let kart = &mut karts[projectile.owner];
+            if projectile.expired() {
+                kart.shielded = false;
+            }
+            return kart.shielded;
+        }
+
+        let hit = karts
+            .iter_mut()
+            .any(|kart| knockout_kart(kart, &projectile.sprite, KART_HIT_DIST, kind.knockout));
+        !hit && !stun_enemy(enemies, &projectile.sprite, kind.enemy_stun) && !projectile.expired()
+    }
+
+    fn update_projectiles(
+        &mut self,
+        karts: &mut [&mut Kart],
+        enemies: &mut [Enemy],
+        level: &Level,
+        dt: f64,
+    ) {
+        for projectile in &mut self.projectiles {
+            let target = projectile.target.map(|target| &karts[target].sprite);
+            projectile.update(dt, target, level);
+        }
+
+        self.projectiles
+            .retain(|projectile| Self::hit_targets(projectile, karts, enemies));
+    }
+
+    pub fn update(
+        &mut self,
+        karts: &mut [&mut Kart],
+        enemies: &mut [Enemy],
+        level: &Level,
+        dt: f64,
+    ) {
+        self.update_powerups(karts, dt);
+        self.update_projectiles(karts, enemies, level, dt);
+    }
+
+    pub fn save_positions(&mut self) {
+        for projectile in &mut self.projectiles {
+            projectile.sprite.save_position();
+        }
+    }
+
+    pub fn sprites(&self) -> impl Iterator<Item = &Sprite> {
+        self.powerups
+            .iter()
+            .map(|powerup| &powerup.sprite)
+            .chain(self.projectiles.iter().map(|projectile| &projectile.sprite))
+    }
+}
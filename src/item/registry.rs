@@ -0,0 +1,189 @@
+use rand::{rngs::StdRng, Rng};
+
+/*
+ * Every item that can come out of a powerup box is listed in ITEMS.
+ * An item says what it looks like, what happens when a kart uses it and
+ * what happens to whatever it hits, so adding an item only needs a new
+ * entry here and its images.
+ * */
+
+//What happens when a kart uses an item
+#[derive(Clone, Copy, PartialEq)]
+pub enum ItemUse {
+    Boost(f64),              //Adds this much to the kart's speed
+    Shield,                  //Protects the kart from the next hit
+    Drop,                    //Leaves the item on the track behind the kart
+    Homing,                  //Fires the item at the kart one place ahead
+    Thrown { bounces: u32 }, //Throws the item forwards, it bounces off walls a few times
+    Strike,                  //Hits every kart ahead of the user at once
+}
+
+pub struct ItemKind {
+    //Image shown while a kart is holding the item
+    pub icon: &'static str,
+    //Image of the item on the track, items that aren't left on the track have none
+    pub sprite: Option<&'static str>,
+    pub size: f64, //Size of the item on the track
+    pub usage: ItemUse,
+    pub speed: f64,      //How fast the item moves once it is used
+    pub lifetime: f64,   //How long the item stays on the track (in seconds)
+    pub max_amount: u16, //Most of the item that a kart gets from one powerup box
+    pub knockout: f64,   //How long a kart that gets hit is knocked out for
+    pub enemy_stun: f64, //How long an enemy that gets hit is stunned for
+}
+
+//How likely each item is to come out of a powerup box, picked before a race
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ItemOdds {
+    Off,      //No powerup boxes on the track
+    Balanced, //Karts near the front get defensive items, karts at the back get faster ones
+    Random,   //Every item is as likely no matter where the kart is
+}
+
+//Index of an item in ITEMS
+#[derive(Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ItemId(pub usize);
+
+impl ItemId {
+    pub fn kind(self) -> &'static ItemKind {
+        &ITEMS[self.0]
+    }
+}
+
+//Weights for rolling each item (in the same order as ITEMS) with balanced odds,
+//the first row is for the leader and the last row is for the kart in last place
+const BALANCED_ODDS: [[u32; ITEMS.len()]; 4] = [
+    [5, 0, 10, 6, 3, 0],
+    [6, 4, 6, 3, 5, 0],
+    [8, 6, 3, 2, 5, 1],
+    [10, 8, 1, 1, 3, 3],
+];
+const RANDOM_ODDS: [[u32; ITEMS.len()]; 1] = [[1; ITEMS.len()]];
+
+impl ItemOdds {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ItemOdds::Off => "Off",
+            ItemOdds::Balanced => "Balanced",
+            ItemOdds::Random => "Random",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            ItemOdds::Off => ItemOdds::Balanced,
+            ItemOdds::Balanced => ItemOdds::Random,
+            ItemOdds::Random => ItemOdds::Off,
+        }
+    }
+
+    fn table(&self) -> &'static [[u32; ITEMS.len()]] {
+        match self {
+            ItemOdds::Off => &[],
+            ItemOdds::Balanced => &BALANCED_ODDS,
+            ItemOdds::Random => &RANDOM_ODDS,
+        }
+    }
+
+    //Picks an item and how many of it a kart in `position` (out of `racers`
+    //karts) gets, a kart racing alone uses the odds for last place
+    pub fn roll(&self, rng: &mut StdRng, position: usize, racers: usize) -> Option<(ItemId, u16)> {
+        let table = self.table();
+        let last_row = table.len().checked_sub(1)?;
+        let row = if racers > 1 && position > 0 {
+            ((position - 1) * last_row + (racers - 1) / 2) / (racers - 1)
+        } else {
+            last_row
+        };
+        let weights = &table[row.min(last_row)];
+
+        let total: u32 = weights.iter().sum();
+        let mut roll = rng.gen_range(0..total.max(1));
+        let index = weights.iter().position(|&weight| {
+            if roll < weight {
+                return true;
+            }
+            roll -= weight;
+            false
+        })?;
+
+        let item = ItemId(index);
+        let amount = rng.gen_range(1..=item.kind().max_amount);
+        Some((item, amount))
+    }
+}
+
+pub const ITEMS: [ItemKind; 6] = [
+    //Speed boost
+    ItemKind {
+        icon: "assets/images/powerups/speedboosticon.png",
+        sprite: None,
+        size: 0.0,
+        usage: ItemUse::Boost(1.0),
+        speed: 0.0,
+        lifetime: 0.0,
+        max_amount: 3,
+        knockout: 0.0,
+        enemy_stun: 0.0,
+    },
+    //Fireball, chases the kart ahead
+    ItemKind {
+        icon: "assets/images/powerups/fireballicon.png",
+        sprite: Some("assets/images/enemies/fireball.png"),
+        size: 24.0 / 256.0,
+        usage: ItemUse::Homing,
+        speed: 4.0,
+        lifetime: 8.0,
+        max_amount: 3,
+        knockout: 2.0,
+        enemy_stun: 3.0,
+    },
+    //Banana, stays on the track until something drives into it
+    ItemKind {
+        icon: "assets/images/powerups/bananaicon.png",
+        sprite: Some("assets/images/enemies/banana.png"),
+        size: 0.06,
+        usage: ItemUse::Drop,
+        speed: 0.0,
+        lifetime: f64::INFINITY,
+        max_amount: 3,
+        knockout: 3.0,
+        enemy_stun: 3.0,
+    },
+    //Shield
+    ItemKind {
+        icon: "assets/images/powerups/shieldicon.png",
+        sprite: Some("assets/images/enemies/shield.png"),
+        size: 0.14,
+        usage: ItemUse::Shield,
+        speed: 0.0,
+        lifetime: 10.0,
+        max_amount: 1,
+        knockout: 0.0,
+        enemy_stun: 0.0,
+    },
+    //Shell, bounces off walls
+    ItemKind {
+        icon: "assets/images/powerups/shellicon.png",
+        sprite: Some("assets/images/enemies/shell.png"),
+        size: 0.08,
+        usage: ItemUse::Thrown { bounces: 3 },
+        speed: 5.0,
+        lifetime: 10.0,
+        max_amount: 2,
+        knockout: 2.0,
+        enemy_stun: 3.0,
+    },
+    //Lightning, knocks out everybody ahead
+    ItemKind {
+        icon: "assets/images/powerups/lightningicon.png",
+        sprite: None,
+        size: 0.0,
+        usage: ItemUse::Strike,
+        speed: 0.0,
+        lifetime: 0.0,
+        max_amount: 1,
+        knockout: 2.0,
+        enemy_stun: 0.0,
+    },
+];
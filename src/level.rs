@@ -1,6 +1,14 @@
-use crate::sprite::{bitmap::BitMap, enemy::Enemy, kart::Kart, powerup::Powerup, Sprite};
+use crate::sprite::{
+    self, bitmap::BitMap, enemy::Enemy, kart::Kart, powerup::Powerup, Sprite, SpriteType,
+};
 use std::collections::HashMap;
-use std::fs::File;
+use std::fs::{self, File};
+use std::path::Path;
+use surface::{Surface, SurfaceTable};
+use track_file::TrackFile;
+
+pub mod surface;
+mod track_file;
 
 pub mod camera_consts {
     pub const DEFAULT_CAM_FOLLOW_DIST: f64 = 1.1;
@@ -9,75 +17,7 @@ pub mod camera_consts {
     pub const DEFAULT_CAM_FAR: f64 = 1.0;
 }
 
-pub const CHECKPOINTS: [(f64, f64); 5] = [
-    (11.0, 35.3),
-    (34.0, 28.0),
-    (32.0, 7.0),
-    (13.0, 6.0),
-    (11.0, 35.3),
-];
-
-pub const ENEMY_LOCATIONS: [(f64, f64); 7] = [
-    (31.0, 18.0),
-    (22.0, 4.0),
-    (22.5, 7.0),
-    (23.0, 4.0),
-    (10.5, 17.0),
-    (7.5, 15.5),
-    (10.5, 16.0),
-];
-
-pub const ENEMY_GOAL_POS: [(f64, f64); 7] = [
-    (31.0, 16.0),
-    (22.0, 7.0),
-    (22.5, 4.0),
-    (23.0, 7.0),
-    (7.5, 16.0),
-    (10.5, 16.5),
-    (7.5, 15.0),
-];
-
-pub const POWERUP_LOCATIONS: [(f64, f64); 11] = [
-    (20.0, 35.2),
-    (20.0, 36.0),
-    (20.0, 34.4),
-    (29.5, 22.5),
-    (29.0, 22.0),
-    (28.5, 21.5),
-    (17.0, 5.0),
-    (16.5, 4.5),
-    (17.5, 4.5),
-    (16.5, 5.5),
-    (17.5, 5.5),
-];
-
-pub fn create_enemies() -> Vec<Enemy> {
-    let mut enemies = vec![];
-
-    for i in 0..ENEMY_LOCATIONS.len() {
-        let enemy = Enemy::new(
-            ENEMY_LOCATIONS[i].0,
-            ENEMY_LOCATIONS[i].1,
-            ENEMY_GOAL_POS[i].0,
-            ENEMY_GOAL_POS[i].1,
-        );
-        enemies.push(enemy);
-    }
-
-    enemies
-}
-
-pub fn create_powerups() -> Vec<Powerup> {
-    let mut powerups = vec![];
-
-    for location in POWERUP_LOCATIONS {
-        let powerup = Powerup::new(location.0, location.1);
-        powerups.push(powerup);
-    }
-
-    powerups
-}
-
+#[derive(Clone, Copy)]
 pub struct Camera {
     pub trans_x: f64,
     pub trans_z: f64,
@@ -94,14 +34,25 @@ pub struct Level {
     level_data: Vec<u8>,
     level_width: u32,
     level_height: u32,
-    pub checkpoints: Vec<(f64, f64)>,
+    surfaces: SurfaceTable,
+    track_textures: HashMap<u32, BitMap>,
+    pub name: String,
+    pub id: String, //File name of the level without the extension, used to name save files
+    pub laps: u32,
+    pub spawn_points: Vec<(f64, f64, f64)>,
+    pub checkpoints: Vec<((f64, f64), (f64, f64))>,
+    pub waypoints: Vec<(f64, f64)>,
+    pub enemy_paths: Vec<((f64, f64), (f64, f64))>,
+    pub powerup_locations: Vec<(f64, f64)>,
     pub level_scale: f64,
 }
 
 impl Level {
-    //Loads the level from a file (a png image)
-    //Does not account for sprites
+    //Loads the level from a file (a png image) along with the track file
+    //that has the same name but a .txt extension (checkpoints, enemies, etc.)
     pub fn load_from_png(path: &str) -> Result<Level, String> {
+        let track_path = Path::new(path).with_extension("txt");
+        let track = TrackFile::load(&track_path.to_string_lossy())?;
         let level_file = File::open(path);
 
         match level_file {
@@ -116,8 +67,20 @@ impl Level {
                     level_data: buff,
                     level_width: info.width,
                     level_height: info.height,
-                    checkpoints: Vec::from(CHECKPOINTS),
-                    level_scale: 32.0,
+                    track_textures: track.surfaces.load_textures()?,
+                    surfaces: track.surfaces,
+                    name: track.name,
+                    id: Path::new(path)
+                        .file_stem()
+                        .map(|stem| stem.to_string_lossy().to_string())
+                        .unwrap_or_default(),
+                    laps: track.laps,
+                    spawn_points: track.spawn_points,
+                    checkpoints: track.checkpoints,
+                    waypoints: track.waypoints,
+                    enemy_paths: track.enemy_paths,
+                    powerup_locations: track.powerup_locations,
+                    level_scale: track.level_scale,
                 })
             }
             Err(msg) => {
@@ -129,13 +92,104 @@ impl Level {
         }
     }
 
+    //Loads every level in a directory, a level is a png image that has
+    //a track file next to it, levels are sorted by their file name and
+    //levels that fail to load are left out
+    pub fn load_tracks(dir: &str) -> Result<Vec<Level>, String> {
+        let mut paths = vec![];
+        for entry in fs::read_dir(dir).map_err(|e| format!("{dir}: {e}"))? {
+            let path = entry.map_err(|e| e.to_string())?.path();
+            if path.extension().is_some_and(|ext| ext == "png")
+                && path.with_extension("txt").exists()
+            {
+                paths.push(path);
+            }
+        }
+        paths.sort();
+
+        //A broken track is skipped so that the other tracks can still be raced on
+        let mut tracks = vec![];
+        for path in &paths {
+            let path = path.to_string_lossy();
+            match Level::load_from_png(&path) {
+                Ok(level) => tracks.push(level),
+                Err(msg) => {
+                    eprintln!("{msg}");
+                    eprintln!("Skipping track: {path}");
+                }
+            }
+        }
+
+        if tracks.is_empty() {
+            return Err(format!("No tracks could be loaded from {dir}"));
+        }
+
+        Ok(tracks)
+    }
+
+    //Returns a top down image of the level that fits in a size x size square
+    //as (ARGB8888 pixels, width, height)
+    pub fn create_thumbnail(&self, size: usize) -> (Vec<u8>, usize, usize) {
+        let level_w = self.level_width as usize;
+        let level_h = self.level_height as usize;
+        let (w, h) = if level_w > level_h {
+            (size, (size * level_h / level_w).max(1))
+        } else {
+            ((size * level_w / level_h).max(1), size)
+        };
+
+        let mut pixels = vec![0u8; w * h * 4];
+        for y in 0..h {
+            for x in 0..w {
+                let ind = (x * level_w / w) * 3 + (y * level_h / h) * 3 * level_w;
+                let offset = y * 4 * w + x * 4;
+                pixels[offset] = self.level_data[ind + 2];
+                pixels[offset + 1] = self.level_data[ind + 1];
+                pixels[offset + 2] = self.level_data[ind];
+                pixels[offset + 3] = 255;
+            }
+        }
+
+        (pixels, w, h)
+    }
+
+    pub fn create_enemies(&self) -> Vec<Enemy> {
+        let mut enemies = vec![];
+
+        for (start, end) in &self.enemy_paths {
+            let enemy = Enemy::new(start.0, start.1, end.0, end.1);
+            enemies.push(enemy);
+        }
+
+        enemies
+    }
+
+    pub fn create_powerups(&self) -> Vec<Powerup> {
+        let mut powerups = vec![];
+
+        for location in &self.powerup_locations {
+            let powerup = Powerup::new(location.0, location.1);
+            powerups.push(powerup);
+        }
+
+        powerups
+    }
+
+    //Creates a kart at one of the spawn points of the level
+    //if there are more karts than spawn points, the spawn points are reused
+    pub fn create_kart(&self, index: usize, spr_type: SpriteType) -> Kart {
+        let (x, z, rotation) = self.spawn_points[index % self.spawn_points.len()];
+        let mut kart = Kart::new(x, z, spr_type);
+        kart.sprite.rotation = rotation;
+        kart
+    }
+
     pub fn display_level(
         &self,
         pixel_buffer: &mut [u8],
         buff_width: usize,
         buff_height: usize,
         cam: &Camera,
-        track_textures: &HashMap<u32, BitMap>,
     ) {
         //The sky
         for y in 0..(buff_height / 8 * 3 + 1) {
@@ -185,7 +239,7 @@ impl Level {
                         | (pixel_buffer[offset + 2] as u32) << 8
                         | 0xff;
 
-                    if let Some(bitmap) = track_textures.get(&pixel_value) {
+                    if let Some(bitmap) = self.track_textures.get(&pixel_value) {
                         bitmap.sample(
                             (trans_x / 16.0).abs().fract(),
                             (trans_z / 16.0).abs().fract(),
@@ -207,7 +261,7 @@ impl Level {
                     | (pixel_buffer[offset + 2] as u32) << 8
                     | 0xff;
 
-                if let Some(bitmap) = track_textures.get(&pixel_value) {
+                if let Some(bitmap) = self.track_textures.get(&pixel_value) {
                     bitmap.sample(
                         (trans_x / 16.0).abs().fract(),
                         (trans_z / 16.0).abs().fract(),
@@ -218,21 +272,56 @@ impl Level {
         }
     }
 
-    pub fn kart_at_checkpoint(&self, spr: &Kart, index: usize, dist: f64) -> bool {
+    //Returns the middle of a checkpoint gate
+    pub fn checkpoint_position(&self, index: usize) -> (f64, f64) {
+        let ((x1, z1), (x2, z2)) = self.checkpoints[index];
+        ((x1 + x2) / 2.0, (z1 + z2) / 2.0)
+    }
+
+    //Returns true if the path from `from` to `to` goes through the checkpoint
+    //gate in the right direction (with the first point of the gate on the left)
+    pub fn crossed_checkpoint(&self, index: usize, from: (f64, f64), to: (f64, f64)) -> bool {
         if index >= self.checkpoints.len() {
             return false;
         }
 
-        ((self.checkpoints[index].0 - spr.sprite.trans_x)
-            * (self.checkpoints[index].0 - spr.sprite.trans_x)
-            + (self.checkpoints[index].1 - spr.sprite.trans_z)
-                * (self.checkpoints[index].1 - spr.sprite.trans_z))
-            .sqrt()
-            < dist
+        let (gate_start, gate_end) = self.checkpoints[index];
+        //Which side of the line each point is on (negative is the right side)
+        let side = |line_start: (f64, f64), line_end: (f64, f64), point: (f64, f64)| {
+            (line_end.0 - line_start.0) * (point.1 - line_start.1)
+                - (line_end.1 - line_start.1) * (point.0 - line_start.0)
+        };
+
+        //The path has to go from the right side of the gate to the left side
+        if side(gate_start, gate_end, from) >= 0.0 || side(gate_start, gate_end, to) < 0.0 {
+            return false;
+        }
+
+        //and the ends of the gate have to be on opposite sides of the path
+        side(from, to, gate_start) * side(from, to, gate_end) <= 0.0
+    }
+
+    //Returns the surface at a position on the level
+    pub fn surface_at(&self, x: f64, z: f64) -> &Surface {
+        let [b, g, r] = self.sample_color(x, z);
+        self.surfaces.get(surface::color_key(r, g, b))
+    }
+
+    //Returns true if karts can't drive through the position
+    pub fn solid_at(&self, x: f64, z: f64) -> bool {
+        self.surface_at(x, z).solid
+    }
+
+    //Returns true if the position is inside of the level image
+    pub fn in_bounds(&self, x: f64, z: f64) -> bool {
+        x >= 0.0
+            && x * self.level_scale < self.level_width as f64
+            && z >= 0.0
+            && z * self.level_scale < self.level_height as f64
     }
 
     pub fn sample_color(&self, x: f64, z: f64) -> [u8; 3] {
-        if x < 0.0 || x > self.level_width as f64 || z < 0.0 || z > self.level_height as f64 {
+        if !self.in_bounds(x, z) {
             return [0, 255, 0]; //Return green (grass) by default
         }
 
@@ -299,6 +388,16 @@ impl Camera {
         }
     }
 
+    //Camera part of the way (0 to 1) from `prev` to this one
+    pub fn interpolated(&self, prev: &Camera, t: f64) -> Camera {
+        Camera {
+            trans_x: prev.trans_x + (self.trans_x - prev.trans_x) * t,
+            trans_z: prev.trans_z + (self.trans_z - prev.trans_z) * t,
+            rotation: sprite::lerp_angle(prev.rotation, self.rotation, t),
+            ..*self
+        }
+    }
+
     //Follow a sprite
     pub fn follow(&mut self, spr: &Sprite, dist: f64) {
         self.trans_x = spr.trans_x - spr.rotation.sin() * dist;
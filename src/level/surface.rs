@@ -0,0 +1,222 @@
+use crate::sprite::bitmap::BitMap;
+use crate::sprite::SpriteType;
+use std::collections::HashMap;
+
+/*
+ * Surfaces describe how karts drive on each color of the level image
+ *
+ * A surface is defined in the track file as:
+ *   surface = <name> <color as rrggbb> [key=value ...]
+ *
+ * with the keys:
+ *   friction = how quickly karts slow down
+ *   max_speed = fastest a kart can go on the surface
+ *   boost = karts are set to this speed when driving on the surface
+ *   knockout = karts are knocked out for this many seconds
+ *   solid = true if karts can't drive through the surface (walls)
+ *   pit = true if karts that drive onto the surface fall in and respawn
+ *   road = true if karts can be respawned on the surface
+ *   texture = ground texture that is drawn for the color
+ *   particle = particle that karts kick up (dust or sparkle)
+ *   sound = sound that karts make when driving on the surface
+ * */
+
+pub struct Surface {
+    pub name: String,
+    pub color: u32,
+    pub friction: f64,
+    pub max_speed: f64,
+    pub boost: Option<f64>,
+    pub knockout: Option<f64>,
+    pub solid: bool,
+    pub pit: bool,
+    pub road: bool,
+    pub texture: Option<String>,
+    pub particle: Option<SpriteType>,
+    #[allow(dead_code)] //The game doesn't play any audio yet
+    pub sound: Option<String>,
+}
+
+pub struct SurfaceTable {
+    surfaces: Vec<Surface>,
+    default_surface: usize, //Used for any color that isn't in the table
+}
+
+//Converts a rgb color to the same format that
+//the level uses to look up textures (BGRA)
+pub fn color_key(r: u8, g: u8, b: u8) -> u32 {
+    (b as u32) << 24 | (g as u32) << 16 | (r as u32) << 8 | 0xff
+}
+
+fn parse_color(hex: &str) -> Result<u32, String> {
+    if hex.len() != 6 {
+        return Err(format!("{hex}: expected a color in the format rrggbb"));
+    }
+
+    let rgb = u32::from_str_radix(hex, 16).map_err(|e| format!("{hex}: {e}"))?;
+    Ok(color_key((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8))
+}
+
+fn parse_particle(name: &str) -> Result<SpriteType, String> {
+    match name {
+        "dust" => Ok(SpriteType::Dust),
+        "sparkle" => Ok(SpriteType::Sparkle),
+        _ => Err(format!("unknown particle `{name}`")),
+    }
+}
+
+impl Surface {
+    pub fn new(name: &str, color: u32, friction: f64, max_speed: f64) -> Self {
+        Self {
+            name: String::from(name),
+            color,
+            friction,
+            max_speed,
+            boost: None,
+            knockout: None,
+            solid: false,
+            pit: false,
+            road: false,
+            texture: None,
+            particle: None,
+            sound: None,
+        }
+    }
+
+    pub fn set_texture(mut self, path: &str) -> Self {
+        self.texture = Some(String::from(path));
+        self
+    }
+
+    pub fn set_boost(mut self, speed: f64) -> Self {
+        self.boost = Some(speed);
+        self
+    }
+
+    pub fn set_solid(mut self) -> Self {
+        self.solid = true;
+        self
+    }
+
+    pub fn set_pit(mut self) -> Self {
+        self.pit = true;
+        self
+    }
+
+    pub fn set_road(mut self) -> Self {
+        self.road = true;
+        self
+    }
+
+    pub fn set_particle(mut self, particle: SpriteType) -> Self {
+        self.particle = Some(particle);
+        self
+    }
+
+    //Parses the values of a surface line in a track file
+    pub fn parse(values: &str) -> Result<Surface, String> {
+        let mut values = values.split_whitespace();
+        let name = values.next().ok_or("surface needs a name")?;
+        let color = parse_color(values.next().ok_or("surface needs a color")?)?;
+        let mut surface = Surface::new(name, color, 0.4, 3.0);
+
+        for value in values {
+            let (key, value) = value
+                .split_once('=')
+                .ok_or(format!("{value}: expected key=value"))?;
+            let number = || match value.parse::<f64>() {
+                Ok(n) if n.is_finite() => Ok(n),
+                Ok(_) => Err(format!("{value}: expected a finite number")),
+                Err(e) => Err(format!("{value}: {e}")),
+            };
+            let boolean = || value.parse::<bool>().map_err(|e| format!("{value}: {e}"));
+
+            match key {
+                "friction" => surface.friction = number()?,
+                "max_speed" => surface.max_speed = number()?,
+                "boost" => surface.boost = Some(number()?),
+                "knockout" => surface.knockout = Some(number()?),
+                "solid" => surface.solid = boolean()?,
+                "pit" => surface.pit = boolean()?,
+                "road" => surface.road = boolean()?,
+                "texture" => surface.texture = Some(String::from(value)),
+                "particle" => surface.particle = Some(parse_particle(value)?),
+                "sound" => surface.sound = Some(String::from(value)),
+                _ => return Err(format!("unknown surface property `{key}`")),
+            }
+        }
+
+        Ok(surface)
+    }
+}
+
+impl SurfaceTable {
+    //The surfaces that every track starts out with
+    pub fn new() -> Self {
+        Self {
+            surfaces: vec![
+                Surface::new("road", color_key(0x70, 0x70, 0x70), 0.4, 3.0)
+                    .set_texture("assets/images/road.png")
+                    .set_road(),
+                Surface::new("grass", color_key(0x00, 0xff, 0x00), 0.1, 0.5)
+                    .set_texture("assets/images/grass.png")
+                    .set_particle(SpriteType::Dust),
+                Surface::new("boost", color_key(0xff, 0xff, 0x00), 0.4, 6.0)
+                    .set_texture("assets/images/speedboost.png")
+                    .set_boost(6.0)
+                    .set_road()
+                    .set_particle(SpriteType::Sparkle),
+                Surface::new("wall", color_key(0xc0, 0x20, 0x20), 0.4, 3.0)
+                    .set_texture("assets/images/wall.png")
+                    .set_solid(),
+                Surface::new("pit", color_key(0x20, 0x10, 0x30), 0.4, 3.0)
+                    .set_texture("assets/images/pit.png")
+                    .set_pit(),
+            ],
+            default_surface: 0,
+        }
+    }
+
+    //Adds a surface to the table, replacing any surface
+    //that has the same name or color
+    pub fn add(&mut self, surface: Surface) {
+        let existing = self
+            .surfaces
+            .iter()
+            .position(|s| s.name == surface.name || s.color == surface.color);
+
+        match existing {
+            Some(index) => self.surfaces[index] = surface,
+            None => self.surfaces.push(surface),
+        }
+    }
+
+    pub fn set_default(&mut self, name: &str) -> Result<(), String> {
+        self.default_surface = self
+            .surfaces
+            .iter()
+            .position(|s| s.name == name)
+            .ok_or(format!("unknown surface `{name}`"))?;
+        Ok(())
+    }
+
+    pub fn get(&self, color: u32) -> &Surface {
+        self.surfaces
+            .iter()
+            .find(|s| s.color == color)
+            .unwrap_or(&self.surfaces[self.default_surface])
+    }
+
+    //Loads the ground textures of every surface, keyed by color
+    pub fn load_textures(&self) -> Result<HashMap<u32, BitMap>, String> {
+        let mut textures = HashMap::<u32, BitMap>::new();
+
+        for surface in &self.surfaces {
+            if let Some(path) = &surface.texture {
+                textures.insert(surface.color, BitMap::from_png(path)?);
+            }
+        }
+
+        Ok(textures)
+    }
+}
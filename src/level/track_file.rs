@@ -0,0 +1,226 @@
+use super::surface::{Surface, SurfaceTable};
+use std::fs;
+
+/*
+ * A track file sits next to the level image (same name, .txt extension)
+ * and describes everything about the track that isn't stored in the pixels.
+ *
+ * Each line is `key = values` where the values are separated by whitespace,
+ * blank lines and lines starting with # are ignored. Positions are in level
+ * units (pixels divided by level_scale).
+ *
+ *   name = <track name>
+ *   level_scale = <pixels per unit>
+ *   laps = <laps to finish the race>
+ *   spawn = <x> <z> [rotation in radians]
+ *   checkpoint = <x1> <z1> <x2> <z2>
+ *   waypoint = <x> <z>
+ *   enemy = <start x> <start z> <end x> <end z>
+ *   powerup = <x> <z>
+ *   surface = <name> <color as rrggbb> [key=value ...]
+ *   default_surface = <name of the surface used for unknown colors>
+ *
+ * spawn, checkpoint, waypoint, enemy and powerup can appear multiple times,
+ * checkpoints are reached in the order that they are listed
+ *
+ * Waypoints are the racing line that computer controlled karts drive along
+ * in order, if there are none then they drive towards the checkpoints
+ *
+ * A checkpoint is a gate going across the road from (x1, z1) to (x2, z2),
+ * karts need to drive through it with (x1, z1) on their left
+ *
+ * Every track starts out with road, grass, boost, wall and pit surfaces,
+ * see surface.rs for how to add new ones or change these
+ * */
+
+const DEFAULT_LEVEL_SCALE: f64 = 32.0;
+const DEFAULT_LAPS: u32 = 4;
+const DEFAULT_SPAWN_ROTATION: f64 = std::f64::consts::PI / 2.0;
+
+pub struct TrackFile {
+    pub name: String,
+    pub level_scale: f64,
+    pub laps: u32,
+    pub spawn_points: Vec<(f64, f64, f64)>,
+    pub checkpoints: Vec<((f64, f64), (f64, f64))>,
+    pub waypoints: Vec<(f64, f64)>,
+    pub enemy_paths: Vec<((f64, f64), (f64, f64))>,
+    pub powerup_locations: Vec<(f64, f64)>,
+    pub surfaces: SurfaceTable,
+}
+
+fn parse_numbers(values: &str, min: usize, max: usize) -> Result<Vec<f64>, String> {
+    let numbers = values
+        .split_whitespace()
+        .map(|v| match v.parse::<f64>() {
+            Ok(n) if n.is_finite() => Ok(n),
+            Ok(_) => Err(format!("{v}: expected a finite number")),
+            Err(e) => Err(format!("{v}: {e}")),
+        })
+        .collect::<Result<Vec<f64>, String>>()?;
+
+    if numbers.len() < min || numbers.len() > max {
+        return Err(format!(
+            "expected {min} to {max} numbers, got {}",
+            numbers.len()
+        ));
+    }
+
+    Ok(numbers)
+}
+
+impl TrackFile {
+    pub fn parse(src: &str) -> Result<TrackFile, String> {
+        let mut track = TrackFile {
+            name: String::from("Unnamed Track"),
+            level_scale: DEFAULT_LEVEL_SCALE,
+            laps: DEFAULT_LAPS,
+            spawn_points: vec![],
+            checkpoints: vec![],
+            waypoints: vec![],
+            enemy_paths: vec![],
+            powerup_locations: vec![],
+            surfaces: SurfaceTable::new(),
+        };
+        let mut default_surface = None;
+
+        for (i, line) in src.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let (key, values) = line
+                .split_once('=')
+                .ok_or(format!("line {}: expected `key = values`", i + 1))?;
+            let values = values.trim();
+
+            let res = match key.trim() {
+                "name" => {
+                    track.name = String::from(values);
+                    Ok(())
+                }
+                "level_scale" => parse_numbers(values, 1, 1).and_then(|n| {
+                    if n[0] <= 0.0 {
+                        return Err(format!("{values}: level_scale needs to be above 0"));
+                    }
+                    track.level_scale = n[0];
+                    Ok(())
+                }),
+                "laps" => values
+                    .parse::<u32>()
+                    .map(|n| track.laps = n)
+                    .map_err(|e| e.to_string()),
+                "spawn" => parse_numbers(values, 2, 3).map(|n| {
+                    let rotation = n.get(2).copied().unwrap_or(DEFAULT_SPAWN_ROTATION);
+                    track.spawn_points.push((n[0], n[1], rotation));
+                }),
+                "checkpoint" => parse_numbers(values, 4, 4)
+                    .map(|n| track.checkpoints.push(((n[0], n[1]), (n[2], n[3])))),
+                "waypoint" => {
+                    parse_numbers(values, 2, 2).map(|n| track.waypoints.push((n[0], n[1])))
+                }
+                "enemy" => parse_numbers(values, 4, 4)
+                    .map(|n| track.enemy_paths.push(((n[0], n[1]), (n[2], n[3])))),
+                "powerup" => {
+                    parse_numbers(values, 2, 2).map(|n| track.powerup_locations.push((n[0], n[1])))
+                }
+                "surface" => Surface::parse(values).map(|s| track.surfaces.add(s)),
+                "default_surface" => {
+                    default_surface = Some(values);
+                    Ok(())
+                }
+                unknown => Err(format!("unknown key `{unknown}`")),
+            };
+
+            res.map_err(|e| format!("line {}: {e}", i + 1))?;
+        }
+
+        if let Some(name) = default_surface {
+            track.surfaces.set_default(name)?;
+        }
+
+        if track.checkpoints.is_empty() {
+            return Err(String::from("track needs at least one checkpoint"));
+        }
+
+        if track.spawn_points.is_empty() {
+            return Err(String::from("track needs at least one spawn point"));
+        }
+
+        Ok(track)
+    }
+
+    pub fn load(path: &str) -> Result<TrackFile, String> {
+        match fs::read_to_string(path) {
+            Ok(src) => TrackFile::parse(&src).map_err(|e| format!("{path}: {e}")),
+            Err(msg) => {
+                eprintln!("{msg}");
+                eprintln!("Cannot open file: {path}");
+                Err(String::from("Failed to open track file"))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn spawn_rotation_is_optional() {
+        let track =
+            TrackFile::parse("checkpoint = 4 0 4 5\nspawn = 1 2\nspawn = 1 3 0.5\n").unwrap();
+        assert_eq!(
+            track.spawn_points,
+            vec![(1.0, 2.0, DEFAULT_SPAWN_ROTATION), (1.0, 3.0, 0.5)]
+        );
+        assert_eq!(track.level_scale, DEFAULT_LEVEL_SCALE);
+        assert_eq!(track.laps, DEFAULT_LAPS);
+    }
+
+    #[test]
+    fn errors_name_the_line() {
+        let src = "# comment\n\nspawn = 1 2\ncheckpoint = 4\n";
+        let err = TrackFile::parse(src).err().unwrap();
+        assert!(err.starts_with("line 4:"), "{err}");
+
+        let err = TrackFile::parse("spawn = 1 2\nfinish = 4 4\n")
+            .err()
+            .unwrap();
+        assert!(err.starts_with("line 2:"), "{err}");
+    }
+
+    #[test]
+    fn numbers_need_to_be_finite() {
+        let src = "checkpoint = 4 0 4 5\nspawn = 1 2\n";
+        for bad in [
+            "spawn = nan 2",
+            "waypoint = 1 inf",
+            "checkpoint = 4 0 -inf 5",
+            "surface = ice a0e0ff friction=nan",
+        ] {
+            assert!(TrackFile::parse(&format!("{src}{bad}\n")).is_err(), "{bad}");
+        }
+        for bad in ["level_scale = 0", "level_scale = -32"] {
+            assert!(TrackFile::parse(&format!("{src}{bad}\n")).is_err(), "{bad}");
+        }
+    }
+
+    #[test]
+    fn tracks_need_a_checkpoint_and_a_spawn_point() {
+        assert!(TrackFile::parse("spawn = 1 2\n").is_err());
+        assert!(TrackFile::parse("checkpoint = 4 0 4 5\n").is_err());
+    }
+
+    #[test]
+    fn the_shipped_tracks_load() {
+        for entry in fs::read_dir("assets/tracks").unwrap() {
+            let path = entry.unwrap().path();
+            if path.extension().is_some_and(|ext| ext == "txt") {
+                let path = path.to_string_lossy();
+                assert!(TrackFile::load(&path).is_ok(), "{path}");
+            }
+        }
+    }
+}
@@ -4,40 +4,77 @@ extern crate sdl2;
 use menu::Text;
 use pausemenu::PauseMenu;
 use sdl2::pixels::{Color, PixelFormatEnum};
-use sdl2::render::BlendMode;
+use sdl2::render::{BlendMode, Texture, TextureCreator};
 use sdl2::surface::Surface;
-use sdl2::video::Window;
-use sprite::load_assets::{load_assets, load_powerup_icon_assets};
+use sdl2::video::{Window, WindowContext};
+use sprite::load_assets::{load_assets, load_kart_assets, load_powerup_icon_assets, load_texture};
 use sprite::SpriteType;
 
 use std::collections::HashMap;
 use std::time::Instant;
 
+mod character;
+mod characterselect;
 mod display;
 mod events;
+mod grandprix;
+mod item;
 mod level;
 mod mainmenu;
 mod menu;
 mod oneplayer;
 mod pausemenu;
+mod race;
+mod records;
+mod recordsscreen;
+mod replay;
+mod replayviewer;
+mod results;
 mod sprite;
+mod standings;
+mod trackselect;
 mod twoplayer;
 
+use character::Character;
+use characterselect::CharacterSelectScreen;
 use events::Events;
+use grandprix::GrandPrix;
 use level::Level;
 use mainmenu::MainMenuScreen;
 use oneplayer::SingeplayerState;
+use records::Records;
+use recordsscreen::RecordsScreen;
+use replay::Replay;
+use replayviewer::ReplayViewer;
+use results::{ResultsChoice, ResultsScreen};
 use sprite::bitmap::BitMap;
+use standings::{StandingsChoice, StandingsScreen};
+use trackselect::TrackSelectScreen;
 use twoplayer::TwoplayerState;
 
+#[derive(Clone, Copy, PartialEq, Eq)]
 enum GameScreen {
     MainMenu,
+    CharacterSelect,
+    TrackSelect,
     OnePlayer,
     TwoPlayer,
+    GrandPrix,
+    Standings,
+    TimeTrial,
+    Results,
+    Records,
+    Replay,
 }
 
 pub const WIDTH: usize = 480;
 pub const HEIGHT: usize = 270;
+//Races are simulated in fixed steps of this length (120 per second)
+//so that they play out the same no matter the frame rate
+pub const TICK: f64 = 1.0 / 120.0;
+//Most ticks run in one frame, after a long hitch the race slows down
+//instead of trying to catch up all at once
+const MAX_TICKS_PER_FRAME: u32 = 12;
 
 fn create_window(sdl_context: &sdl2::Sdl) -> Result<Window, String> {
     let vid_subsystem = sdl_context.video().map_err(|e| e.to_string())?;
@@ -58,25 +95,100 @@ fn create_window(sdl_context: &sdl2::Sdl) -> Result<Window, String> {
     Ok(window)
 }
 
-fn load_track_textures() -> Result<HashMap<u32, BitMap>, String> {
-    //BGRA
-    let mut track_textures = HashMap::<u32, BitMap>::new();
-    track_textures.insert(
-        0x707070ff,
-        BitMap::from_png("assets/images/road.png").map_err(|e| e.to_string())?,
-    );
-    track_textures.insert(
-        0x00ff00ff,
-        BitMap::from_png("assets/images/grass.png").map_err(|e| e.to_string())?,
-    );
-    track_textures.insert(
-        0x00ffffff,
-        BitMap::from_png("assets/images/speedboost.png").map_err(|e| e.to_string())?,
-    );
-    Ok(track_textures)
+//Sets up a single player race on a track with the rules from the track select screen
+fn init_single_player(
+    mode: GameScreen,
+    level: &Level,
+    track_select: &TrackSelectScreen,
+    racers: &[&Character],
+) -> SingeplayerState {
+    if mode == GameScreen::TimeTrial {
+        SingeplayerState::init_time_trial(level, track_select.rules, racers[0])
+    } else {
+        SingeplayerState::init(level, track_select.rules, track_select.difficulty, racers)
+    }
+}
+
+//Reads `--seed <number>` from the command line so that races can be played
+//again with the same item rolls, every race gets a random seed without it
+fn seed_from_args() -> Result<Option<u64>, String> {
+    let args: Vec<String> = std::env::args().collect();
+    match args.iter().position(|arg| arg == "--seed") {
+        Some(i) => args
+            .get(i + 1)
+            .ok_or("--seed needs a number")?
+            .parse::<u64>()
+            .map(Some)
+            .map_err(|e| format!("--seed: {e}")),
+        None => Ok(None),
+    }
+}
+
+//Reads `--replay <file>` from the command line to start the game by watching a replay
+fn replay_from_args() -> Result<Option<String>, String> {
+    let args: Vec<String> = std::env::args().collect();
+    match args.iter().position(|arg| arg == "--replay") {
+        Some(i) => args
+            .get(i + 1)
+            .cloned()
+            .map(Some)
+            .ok_or(String::from("--replay needs a file")),
+        None => Ok(None),
+    }
+}
+
+//Sets up the karts and track of a replay to watch it,
+//returns the viewer and the index of the track
+fn watch_replay<'a>(
+    replay: Replay,
+    tracks: &[Level],
+    characters: &[Character],
+    exit_screen: GameScreen,
+    sprite_assets: &mut HashMap<SpriteType, Texture<'a>>,
+    texture_creator: &'a TextureCreator<WindowContext>,
+) -> Result<(ReplayViewer, usize), String> {
+    let track = replay.track_index(tracks)?;
+    let racers = replay.racers(characters)?;
+    load_kart_assets(sprite_assets, &racers, texture_creator)?;
+    let viewer = ReplayViewer::init(replay, &tracks[track], &racers, exit_screen);
+    Ok((viewer, track))
+}
+
+//Saves the replay of the last race and returns where it was saved
+fn save_replay(replay: &Replay) -> Option<String> {
+    replay
+        .save()
+        .map_err(|msg| {
+            eprintln!("{msg}");
+            eprintln!("Cannot save replay");
+        })
+        .ok()
+}
+
+fn race_seed(seed_arg: Option<u64>) -> u64 {
+    seed_arg.unwrap_or_else(rand::random)
+}
+
+//Adds the length of the last frame to the time that hasn't been simulated
+//yet and returns how many ticks to run (at most max_ticks), the leftover
+//time stays in `accumulator`
+fn count_ticks(accumulator: &mut f64, frame_time: f64, max_ticks: u32) -> u32 {
+    *accumulator += frame_time;
+    let ticks = (*accumulator / TICK).floor() as u32;
+    *accumulator -= ticks as f64 * TICK;
+
+    if ticks > max_ticks {
+        *accumulator = 0.0;
+        return max_ticks;
+    }
+
+    ticks
 }
 
 fn main() -> Result<(), String> {
+    let seed_arg = seed_from_args()?;
+    let replay_arg = replay_from_args()?;
+
     // Initialize SDL2
     let sdl_context = sdl2::init().map_err(|e| e.to_string())?;
     //Create window
@@ -103,23 +215,82 @@ fn main() -> Result<(), String> {
     let font = font_ctx
         .load_font("assets/fonts/8BitOperator/8bitOperatorPlus-Regular.ttf", 32)
         .map_err(|e| e.to_string())?;
-    //Load track textures
-    let track_textures = load_track_textures()?;
     //Load other textures
-    let sprite_assets = load_assets(&texture_creator)?;
+    let mut sprite_assets = load_assets(&texture_creator)?;
     let powerup_assets = load_powerup_icon_assets(&texture_creator)?;
-    //Load level
-    let track = Level::load_from_png("assets/level.png")?;
+    //Load karts
+    let characters = character::load_characters(character::CHARACTERS_PATH)?;
+    let kart_previews = characters
+        .iter()
+        .map(|character| load_texture(&character.sprite, &texture_creator))
+        .collect::<Result<Vec<_>, String>>()?;
+    //Load levels
+    let tracks = Level::load_tracks("assets/tracks")?;
+    let thumbnails = trackselect::create_thumbnails(&tracks, &texture_creator)?;
+    let mut current_track = 0;
 
     let mut fps_update_timer = 0.0;
     let mut fps = 0.0f64;
     let mut frames = 0u32;
     let mut sec_per_frame: f64 = 0.0;
+    let mut tick_accumulator = 0.0;
 
     let mut screen = GameScreen::MainMenu;
     let mut main_menu = MainMenuScreen::init();
-    let mut single_player_state = SingeplayerState::init();
-    let mut two_player_state = TwoplayerState::init();
+    let mut character_select = CharacterSelectScreen::init(GameScreen::OnePlayer, &characters);
+    let mut track_select = TrackSelectScreen::init(GameScreen::OnePlayer, &tracks);
+    let racers = character_select.racers(&characters);
+    let mut single_player_state = SingeplayerState::init(
+        &tracks[current_track],
+        track_select.rules,
+        track_select.difficulty,
+        &racers,
+    );
+    let mut two_player_state =
+        TwoplayerState::init(&tracks[current_track], track_select.rules, &racers);
+    let mut grand_prix = GrandPrix::new(
+        current_track,
+        tracks.len(),
+        track_select.rules,
+        track_select.difficulty,
+        single_player_state.racer_names(),
+    );
+    let mut standings = StandingsScreen::init(track_select.rules.seed);
+    let mut records = Records::load(records::RECORDS_PATH).unwrap_or_else(|msg| {
+        eprintln!("{msg}");
+        Records::not_loaded()
+    });
+    let mut results = ResultsScreen::init(
+        GameScreen::OnePlayer,
+        &tracks[current_track],
+        track_select.rules,
+        vec![],
+        &records,
+    );
+    let mut records_screen = RecordsScreen::init(&tracks);
+    //The race being played is recorded so that it can be watched again
+    let mut replay = Replay::new(
+        GameScreen::OnePlayer,
+        &tracks[current_track],
+        track_select.rules,
+        track_select.difficulty,
+        &racers,
+    );
+    let mut replay_viewer = None;
+    if let Some(path) = replay_arg {
+        let (viewer, track) = watch_replay(
+            Replay::load(&path)?,
+            &tracks,
+            &characters,
+            GameScreen::MainMenu,
+            &mut sprite_assets,
+            &texture_creator,
+        )
+        .map_err(|e| format!("{path}: {e}"))?;
+        replay_viewer = Some(viewer);
+        current_track = track;
+        screen = GameScreen::Replay;
+    }
 
     //buttons
     let mut pause_menu = PauseMenu::new();
@@ -130,69 +301,267 @@ fn main() -> Result<(), String> {
         canvas.set_draw_color(Color::RGB(32, 128, 255));
         canvas.clear();
         let canvas_dimensions = canvas.output_size()?;
+        let track = &tracks[current_track];
 
         match screen {
             GameScreen::MainMenu => {
                 main_menu.update_camera(sec_per_frame);
-                main_menu.create_background_texture(
-                    &track,
-                    &mut pixel_buffer,
-                    &mut texture,
-                    &track_textures,
-                )?;
+                main_menu.create_background_texture(&tracks[0], &mut pixel_buffer, &mut texture)?;
                 main_menu.display(&mut canvas, &texture, &texture_creator, &events, &font)?;
                 let selected_screen = main_menu.press_buttons(&mut events, canvas_dimensions);
 
-                if let Some(selected_screen) = selected_screen {
-                    screen = selected_screen;
+                if let Some(GameScreen::Records) = selected_screen {
+                    screen = GameScreen::Records;
+                    records_screen = RecordsScreen::init(&tracks);
+                } else if let Some(selected_screen) = selected_screen {
+                    screen = GameScreen::CharacterSelect;
                     main_menu = MainMenuScreen::init();
-                    single_player_state = SingeplayerState::init();
-                    two_player_state = TwoplayerState::init();
+                    character_select = CharacterSelectScreen::init(selected_screen, &characters);
+                    track_select = TrackSelectScreen::init(selected_screen, &tracks);
+                }
+            }
+            GameScreen::CharacterSelect => {
+                character_select.display(
+                    &mut canvas,
+                    &texture_creator,
+                    &events,
+                    &font,
+                    &characters,
+                    &kart_previews,
+                )?;
+
+                if let Some(selected_screen) =
+                    character_select.press_buttons(&mut events, canvas_dimensions, &characters)
+                {
+                    screen = selected_screen;
                 }
             }
-            GameScreen::OnePlayer => {
+            GameScreen::TrackSelect => {
+                track_select.display(
+                    &mut canvas,
+                    &texture_creator,
+                    &events,
+                    &font,
+                    &tracks,
+                    &thumbnails,
+                )?;
+                let selected_screen =
+                    track_select.press_buttons(&mut events, canvas_dimensions, &tracks);
+
+                if let Some(GameScreen::CharacterSelect) = selected_screen {
+                    screen = GameScreen::CharacterSelect;
+                } else if let Some(selected_screen) = selected_screen {
+                    screen = selected_screen;
+                    current_track = track_select.selected;
+                    track_select.rules.seed = race_seed(seed_arg);
+                    let racers = character_select.racers(&characters);
+                    load_kart_assets(&mut sprite_assets, &racers, &texture_creator)?;
+                    single_player_state = init_single_player(
+                        selected_screen,
+                        &tracks[current_track],
+                        &track_select,
+                        &racers,
+                    );
+                    two_player_state =
+                        TwoplayerState::init(&tracks[current_track], track_select.rules, &racers);
+                    replay = Replay::new(
+                        selected_screen,
+                        &tracks[current_track],
+                        track_select.rules,
+                        track_select.difficulty,
+                        &racers,
+                    );
+                    //Time left over from the last screen isn't part of the race
+                    tick_accumulator = 0.0;
+                    grand_prix = GrandPrix::new(
+                        current_track,
+                        tracks.len(),
+                        track_select.rules,
+                        track_select.difficulty,
+                        single_player_state.racer_names(),
+                    );
+                }
+            }
+            GameScreen::OnePlayer | GameScreen::GrandPrix | GameScreen::TimeTrial => {
                 pause_menu.listen_for_escape(&mut events);
 
+                if !pause_menu.paused {
+                    let ticks =
+                        count_ticks(&mut tick_accumulator, sec_per_frame, MAX_TICKS_PER_FRAME);
+                    for _ in 0..ticks {
+                        let input = SingeplayerState::read_input(&mut events);
+                        replay.record(&[input]);
+                        single_player_state.update(&input, track, TICK);
+                    }
+                    single_player_state.set_interpolation(tick_accumulator / TICK);
+                }
+
                 single_player_state.create_background_texture(
                     &mut pixel_buffer,
-                    &track,
-                    &track_textures,
+                    track,
                     &mut texture,
                 )?;
                 single_player_state.display(&mut canvas, &texture)?;
-                single_player_state.display_sprites(&mut canvas, &sprite_assets)?;
-                single_player_state.display_hud(&mut canvas, &texture_creator, &font)?;
+                single_player_state.display_sprites(&mut canvas, &mut sprite_assets)?;
+                single_player_state.display_hud(
+                    &mut canvas,
+                    &texture_creator,
+                    &font,
+                    &powerup_assets,
+                )?;
 
-                if !pause_menu.paused {
-                    single_player_state.update(&events, &track, sec_per_frame);
+                //Leave the race a little while after the player finishes
+                if single_player_state.results_ready() {
+                    if screen == GameScreen::GrandPrix {
+                        grand_prix.award_points(&single_player_state.positions());
+                        standings = StandingsScreen::init(replay.rules.seed);
+                        screen = GameScreen::Standings;
+                    } else {
+                        results = ResultsScreen::init(
+                            screen,
+                            track,
+                            track_select.rules,
+                            single_player_state.results(),
+                            &records,
+                        );
+                        screen = GameScreen::Results;
+                    }
                 }
             }
-            GameScreen::TwoPlayer => {
-                pause_menu.listen_for_escape(&mut events);
+            GameScreen::Results => {
+                results.display(&mut canvas, &texture_creator, &events, &font)?;
 
-                let sz = pixel_buffer.len() / 2;
-                let canvas_dimensions = canvas.output_size()?;
-                let canvas_texture_rect =
-                    display::calculate_texture_rect(canvas_dimensions, WIDTH, HEIGHT);
-                two_player_state.create_background_texture(
-                    &mut pixel_buffer,
-                    &track,
-                    &track_textures,
-                );
-                two_player_state.display_background(
+                let choice = results.press_buttons(&mut events, canvas_dimensions, &mut records);
+
+                if choice == Some(ResultsChoice::MainMenu) {
+                    screen = GameScreen::MainMenu;
+                } else if choice == Some(ResultsChoice::SaveReplay) {
+                    if let Some(path) = save_replay(&replay) {
+                        results.replay_saved(path);
+                    }
+                } else if choice == Some(ResultsChoice::WatchReplay) {
+                    let (viewer, _) = watch_replay(
+                        replay.clone(),
+                        &tracks,
+                        &characters,
+                        GameScreen::Results,
+                        &mut sprite_assets,
+                        &texture_creator,
+                    )?;
+                    replay_viewer = Some(viewer);
+                    screen = GameScreen::Replay;
+                } else if let Some(choice) = choice {
+                    //Retrying uses the same seed so the race is a fair rematch
+                    if choice == ResultsChoice::NextTrack {
+                        current_track = (current_track + 1) % tracks.len();
+                        track_select.rules.seed = race_seed(seed_arg);
+                    }
+                    let racers = character_select.racers(&characters);
+                    single_player_state = init_single_player(
+                        results.mode,
+                        &tracks[current_track],
+                        &track_select,
+                        &racers,
+                    );
+                    two_player_state =
+                        TwoplayerState::init(&tracks[current_track], track_select.rules, &racers);
+                    replay = Replay::new(
+                        results.mode,
+                        &tracks[current_track],
+                        track_select.rules,
+                        track_select.difficulty,
+                        &racers,
+                    );
+                    tick_accumulator = 0.0;
+                    screen = results.mode;
+                }
+            }
+            GameScreen::Records => {
+                records_screen.display(
                     &mut canvas,
-                    &pixel_buffer[..sz],
-                    &mut texture,
-                    0,
+                    &texture_creator,
+                    &events,
+                    &font,
+                    &tracks,
+                    &records,
                 )?;
-                two_player_state.display_sprites(&mut canvas, &sprite_assets, SpriteType::Kart1)?;
-                two_player_state.display_background(
+
+                if records_screen.press_buttons(&mut events, canvas_dimensions, &tracks) {
+                    screen = GameScreen::MainMenu;
+                }
+            }
+            GameScreen::Standings => {
+                standings.display(
                     &mut canvas,
-                    &pixel_buffer[sz..],
+                    &texture_creator,
+                    &events,
+                    &font,
+                    &grand_prix,
+                    &tracks,
+                )?;
+
+                let choice = standings.press_buttons(&mut events, canvas_dimensions);
+
+                if choice == Some(StandingsChoice::SaveReplay) {
+                    if let Some(path) = save_replay(&replay) {
+                        standings.replay_saved(path);
+                    }
+                } else if choice == Some(StandingsChoice::WatchReplay) {
+                    let (viewer, _) = watch_replay(
+                        replay.clone(),
+                        &tracks,
+                        &characters,
+                        GameScreen::Standings,
+                        &mut sprite_assets,
+                        &texture_creator,
+                    )?;
+                    replay_viewer = Some(viewer);
+                    screen = GameScreen::Replay;
+                } else if choice == Some(StandingsChoice::Continue) {
+                    if grand_prix.finished() {
+                        screen = GameScreen::MainMenu;
+                    } else {
+                        current_track = grand_prix.current_track();
+                        let racers = character_select.racers(&characters);
+                        single_player_state = SingeplayerState::init(
+                            &tracks[current_track],
+                            grand_prix.round_rules(),
+                            grand_prix.difficulty,
+                            &racers,
+                        );
+                        replay = Replay::new(
+                            GameScreen::GrandPrix,
+                            &tracks[current_track],
+                            grand_prix.round_rules(),
+                            grand_prix.difficulty,
+                            &racers,
+                        );
+                        tick_accumulator = 0.0;
+                        screen = GameScreen::GrandPrix;
+                    }
+                }
+            }
+            GameScreen::TwoPlayer => {
+                pause_menu.listen_for_escape(&mut events);
+
+                if !pause_menu.paused {
+                    let ticks =
+                        count_ticks(&mut tick_accumulator, sec_per_frame, MAX_TICKS_PER_FRAME);
+                    for _ in 0..ticks {
+                        let inputs = TwoplayerState::read_inputs(&mut events);
+                        replay.record(&inputs);
+                        two_player_state.update(track, &inputs, TICK);
+                    }
+                    two_player_state.set_interpolation(tick_accumulator / TICK);
+                }
+
+                two_player_state.display_views(
+                    &mut canvas,
+                    track,
+                    &mut pixel_buffer,
                     &mut texture,
-                    canvas_texture_rect.height() / 2,
+                    &mut sprite_assets,
                 )?;
-                two_player_state.display_sprites(&mut canvas, &sprite_assets, SpriteType::Kart2)?;
                 two_player_state.display_hud(
                     &mut canvas,
                     &texture_creator,
@@ -200,9 +569,32 @@ fn main() -> Result<(), String> {
                     &powerup_assets,
                 )?;
 
-                if !pause_menu.paused {
-                    two_player_state.use_powerups(&mut events);
-                    two_player_state.update(&track, &events, sec_per_frame);
+                if two_player_state.results_ready() {
+                    results = ResultsScreen::init(
+                        screen,
+                        track,
+                        track_select.rules,
+                        two_player_state.results(),
+                        &records,
+                    );
+                    screen = GameScreen::Results;
+                }
+            }
+            GameScreen::Replay => {
+                if let Some(viewer) = &mut replay_viewer {
+                    viewer.update(track, sec_per_frame);
+                    viewer.display_race(
+                        &mut canvas,
+                        track,
+                        &mut pixel_buffer,
+                        &mut texture,
+                        &mut sprite_assets,
+                    )?;
+                    viewer.display_hud(&mut canvas, &texture_creator, &font, &powerup_assets)?;
+
+                    if viewer.press_buttons(&mut events) {
+                        screen = viewer.exit_screen;
+                    }
                 }
             }
         }
@@ -1,12 +1,3 @@
-use sdl2::{
-    pixels::Color,
-    rect::{Point, Rect},
-    render::{Canvas, Texture, TextureCreator},
-    ttf::Font,
-    video::{Window, WindowContext},
-};
-use std::collections::HashMap;
-
 use crate::{
     display,
     events::Events,
@@ -15,15 +6,24 @@ use crate::{
         Camera, Level,
     },
     menu::{Button, Text},
-    sprite::bitmap::BitMap,
     GameScreen, HEIGHT, WIDTH,
 };
+use sdl2::{
+    pixels::Color,
+    rect::{Point, Rect},
+    render::{Canvas, Texture, TextureCreator},
+    ttf::Font,
+    video::{Window, WindowContext},
+};
 
 const MENU_CAMERA_ROTATION_SPEED: f64 = 0.2;
 
 pub struct MainMenuScreen {
     oneplayer_button: Button,
     twoplayer_button: Button,
+    grandprix_button: Button,
+    timetrial_button: Button,
+    records_button: Button,
     quit_button: Button,
     camera: Camera,
 }
@@ -33,7 +33,10 @@ impl MainMenuScreen {
         Self {
             oneplayer_button: Button::new(0, -32, "One Player"),
             twoplayer_button: Button::new(0, 16, "Two Player"),
-            quit_button: Button::new(0, 64, "QUIT"),
+            grandprix_button: Button::new(0, 64, "Grand Prix"),
+            timetrial_button: Button::new(0, 112, "Time Trial"),
+            records_button: Button::new(0, 160, "Records"),
+            quit_button: Button::new(0, 208, "QUIT"),
             camera: Camera::new(
                 9.0,
                 35.5,
@@ -61,6 +64,12 @@ impl MainMenuScreen {
             return Some(GameScreen::OnePlayer);
         } else if self.twoplayer_button.clicked(events, canvas_dimensions) {
             return Some(GameScreen::TwoPlayer);
+        } else if self.grandprix_button.clicked(events, canvas_dimensions) {
+            return Some(GameScreen::GrandPrix);
+        } else if self.timetrial_button.clicked(events, canvas_dimensions) {
+            return Some(GameScreen::TimeTrial);
+        } else if self.records_button.clicked(events, canvas_dimensions) {
+            return Some(GameScreen::Records);
         }
 
         None
@@ -71,16 +80,9 @@ impl MainMenuScreen {
         level: &Level,
         pixel_buffer: &mut [u8],
         texture: &mut Texture,
-        track_textures: &HashMap<u32, BitMap>,
     ) -> Result<(), String> {
         //Background
-        level.display_level(
-            pixel_buffer,
-            WIDTH,
-            HEIGHT / 2,
-            &self.camera,
-            track_textures,
-        );
+        level.display_level(pixel_buffer, WIDTH, HEIGHT / 2, &self.camera);
 
         texture
             .update(None, pixel_buffer, WIDTH * 4)
@@ -119,6 +121,12 @@ impl MainMenuScreen {
             .display(canvas, texture_creator, events, font)?;
         self.twoplayer_button
             .display(canvas, texture_creator, events, font)?;
+        self.grandprix_button
+            .display(canvas, texture_creator, events, font)?;
+        self.timetrial_button
+            .display(canvas, texture_creator, events, font)?;
+        self.records_button
+            .display(canvas, texture_creator, events, font)?;
         self.quit_button
             .display(canvas, texture_creator, events, font)?;
 
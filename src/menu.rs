@@ -1,4 +1,5 @@
 use crate::events;
+use sdl2::keyboard::Keycode;
 use sdl2::pixels::Color;
 use sdl2::rect::Rect;
 use sdl2::render::{Canvas, Texture, TextureCreator};
@@ -134,7 +135,7 @@ impl Text {
         &'a self,
         font: &Font,
         texture_creator: &'a TextureCreator<WindowContext>,
-    ) -> Result<Texture, String> {
+    ) -> Result<Texture<'a>, String> {
         let font_surface = font
             .render(self.text.as_str())
             .solid(self.color)
@@ -145,3 +146,66 @@ impl Text {
         Ok(font_texture)
     }
 }
+
+//A line of text that the player can type into
+pub struct TextField {
+    pub text: String,
+    max_len: usize,
+    // x and y are relative to the center of the screen
+    x: i32,
+    y: i32,
+}
+
+impl TextField {
+    pub fn new(fieldx: i32, fieldy: i32, max_len: usize) -> TextField {
+        TextField {
+            text: String::new(),
+            max_len,
+            x: fieldx,
+            y: fieldy,
+        }
+    }
+
+    pub fn update(&mut self, events: &mut events::Events) {
+        if events.key_is_pressed_once(Keycode::Backspace) {
+            self.text.pop();
+        }
+
+        for c in events.text_input.chars() {
+            //The font only has ascii characters
+            if self.text.len() < self.max_len && c.is_ascii() && !c.is_ascii_control() {
+                self.text.push(c);
+            }
+        }
+    }
+
+    pub fn display(
+        &self,
+        canvas: &mut Canvas<Window>,
+        texture_creator: &TextureCreator<WindowContext>,
+        font: &Font,
+    ) -> Result<(), String> {
+        let (canv_w, canv_h) = canvas.output_size()?;
+        let width = (self.max_len as u32 + 1) * 24;
+        let field_rect = Rect::new(
+            (canv_w / 2) as i32 + self.x - width as i32 / 2 - 8,
+            (canv_h / 2) as i32 + self.y - 28,
+            width + 16,
+            56,
+        );
+        canvas.set_draw_color(Color::RGBA(0, 0, 0, 128));
+        canvas.fill_rect(field_rect).map_err(|e| e.to_string())?;
+
+        //Show a cursor at the end of the text
+        let text = Text::new(
+            format!("{}_", self.text).as_str(),
+            (canv_w / 2) as i32 + self.x,
+            (canv_h / 2) as i32 + self.y - 24,
+            Color::WHITE,
+            24,
+        );
+        text.display_center(canvas, texture_creator, font)?;
+
+        Ok(())
+    }
+}
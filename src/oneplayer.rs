@@ -1,28 +1,92 @@
+use crate::character::Character;
 use crate::events::Events;
+use crate::item::Items;
 use crate::level::{
     camera_consts::{DEFAULT_CAM_FAR, DEFAULT_CAM_FOLLOW_DIST, DEFAULT_CAM_FOV, DEFAULT_CAM_NEAR},
-    create_enemies, Camera, Level, CHECKPOINTS,
+    Camera, Level,
+};
+use crate::race::{self, RaceRules, RacerResult};
+use crate::sprite::{
+    ai_kart::{AiKart, Difficulty},
+    dist_between,
+    enemy::Enemy,
+    ghost::{self, Ghost, GhostRecorder},
+    kart::{self, Kart, KartInput},
+    load_assets::KART_SLOTS,
+    particle::Particle,
+    Sprite, SpriteType, SPRITE_SIZE,
 };
-use crate::sprite::{dist_between, enemy::Enemy, kart::Kart, Sprite, SpriteType, SPRITE_SIZE};
 use sdl2::keyboard::Keycode;
 
 mod oneplayer_display;
 
-const START_TIMER: f64 = 3.0;
+//How long to wait after the player finishes before leaving the race
+const RESULTS_DELAY: f64 = 3.0;
 
 pub struct SingeplayerState {
     player_kart: Kart,
+    ai_karts: Vec<AiKart>,
     cam: Camera,
+    prev_cam: Camera,   //Camera at the start of the last tick
+    interpolation: f64, //How far between the last two ticks to draw everything
+    items: Items,
     enemies: Vec<Enemy>,
+    particles: Vec<Particle>,
     checkpoint: Sprite,
     start_timer: f64,
     timer: f64,
+    finish_timer: f64, //Time since the player finished
+    rules: RaceRules,
+    ghost: Option<Ghost>,            //Best run on the track in a time trial
+    recorder: Option<GhostRecorder>, //Records the player in a time trial
+    new_best: bool,
+    racer_names: Vec<String>, //The player followed by the computer karts
+    watching: usize,          //Index of the kart that the camera follows (0 is the player)
+    camera_switched: bool,    //Redraws the level after switching karts in a replay
 }
 
 impl SingeplayerState {
-    pub fn init() -> Self {
-        let mut kart = Kart::new(9.0, 35.5, SpriteType::Kart1);
-        kart.move_kart(0.0);
+    //Race against the computer karts, the player drives the
+    //first racer and the computer drives the rest
+    pub fn init(
+        level: &Level,
+        rules: RaceRules,
+        difficulty: Difficulty,
+        racers: &[&Character],
+    ) -> Self {
+        let ai_karts = KART_SLOTS
+            .iter()
+            .zip(racers)
+            .enumerate()
+            .skip(1)
+            .map(|(i, (&spr_type, racer))| {
+                let kart = level.create_kart(i, spr_type).set_stats(racer.stats);
+                AiKart::new(kart, difficulty, level)
+            })
+            .collect();
+
+        let mut state = Self::create(level, rules, racers[0], ai_karts);
+        state
+            .racer_names
+            .extend(racers.iter().skip(1).map(|racer| racer.name.clone()));
+        state.save_positions();
+        state
+    }
+
+    //Race alone against the ghost of the best run on the track
+    pub fn init_time_trial(level: &Level, rules: RaceRules, racer: &Character) -> Self {
+        let mut state = Self::create(level, rules, racer, vec![]);
+        state.ghost = Ghost::load(&ghost::ghost_path(level, rules.laps)).ok();
+        state.recorder = Some(GhostRecorder::new());
+        state.save_positions();
+        state
+    }
+
+    fn create(level: &Level, rules: RaceRules, racer: &Character, ai_karts: Vec<AiKart>) -> Self {
+        let mut kart = level
+            .create_kart(0, SpriteType::Kart1)
+            .set_stats(racer.stats);
+        kart.move_kart(0.0, level);
 
         let camera = Camera::create_following(
             &kart.sprite,
@@ -34,74 +98,333 @@ impl SingeplayerState {
 
         Self {
             player_kart: kart,
+            ai_karts,
             cam: camera,
-            enemies: create_enemies(),
-            checkpoint: Sprite::from_tuple(CHECKPOINTS[0], SpriteType::Checkpoint1)
+            prev_cam: camera,
+            interpolation: 1.0,
+            items: Items::new(level, rules),
+            enemies: if rules.enemies {
+                level.create_enemies()
+            } else {
+                vec![]
+            },
+            particles: vec![],
+            checkpoint: Sprite::from_tuple(level.checkpoint_position(0), SpriteType::Checkpoint1)
                 .set_size(SPRITE_SIZE, SPRITE_SIZE),
-            start_timer: START_TIMER,
+            start_timer: rules.countdown,
             timer: 0.0,
+            finish_timer: 0.0,
+            rules,
+            ghost: None,
+            recorder: None,
+            new_best: false,
+            racer_names: vec![String::from("You")],
+            watching: 0,
+            camera_switched: false,
         }
     }
 
-    fn update_kart(&mut self, events: &Events, level: &Level, dt: f64) {
+    //Plays the race back from recorded inputs without
+    //touching the saved ghost of the track
+    pub fn set_replay(mut self) -> Self {
+        self.ghost = None;
+        self.recorder = None;
+        self
+    }
+
+    //The player's controls for one tick
+    pub fn read_input(events: &mut Events) -> KartInput {
+        KartInput::from_keys(
+            events,
+            Keycode::Up,
+            Keycode::RShift,
+            Keycode::RCtrl,
+            Keycode::Left,
+            Keycode::Right,
+            Keycode::Down,
+        )
+    }
+
+    fn update_kart(&mut self, input: &KartInput, level: &Level, dt: f64) {
         //Move the kart
-        if self.start_timer <= 0.0 && self.player_kart.laps < 4 {
-            self.player_kart
-                .drive_kart(events, Keycode::Up, Keycode::Left, Keycode::Right);
-            self.player_kart.move_kart(dt);
+        if self.start_timer <= 0.0 && self.player_kart.laps < self.rules.laps {
+            self.player_kart.drive_kart(input);
+            self.player_kart.move_kart(dt, level);
+        }
+
+        if self.player_kart.update_checkpoint(level, self.timer) {
+            (self.checkpoint.trans_x, self.checkpoint.trans_z) =
+                level.checkpoint_position(self.player_kart.current_checkpoint);
+        }
+        self.player_kart.update_wrong_way(level, dt);
+
+        self.player_kart.apply_friction(level);
+
+        if self.player_kart.laps == self.rules.laps {
+            self.player_kart.stop();
         }
+    }
 
-        self.player_kart.sprite.camera_kart = !self.player_kart.knocked_out();
-        if level.kart_at_checkpoint(&self.player_kart, self.player_kart.current_checkpoint, 1.0) {
-            if self.player_kart.current_checkpoint == level.checkpoints.len() - 1 {
-                self.player_kart.laps += 1;
+    fn update_ai_karts(&mut self, level: &Level, dt: f64) {
+        for ai_kart in &mut self.ai_karts {
+            if self.start_timer <= 0.0 && ai_kart.kart.laps < self.rules.laps {
+                let input = ai_kart.get_input(level);
+                ai_kart.kart.drive_kart(&input);
+                ai_kart.kart.move_kart(dt, level);
             }
 
-            self.player_kart.current_checkpoint += 1;
-            self.player_kart.current_checkpoint %= level.checkpoints.len();
-            self.checkpoint.trans_x = level.checkpoints[self.player_kart.current_checkpoint].0;
-            self.checkpoint.trans_z = level.checkpoints[self.player_kart.current_checkpoint].1;
+            ai_kart.kart.update_checkpoint(level, self.timer);
+            ai_kart.kart.apply_friction(level);
+
+            if ai_kart.kart.laps == self.rules.laps {
+                ai_kart.kart.stop();
+            }
         }
+    }
 
-        self.player_kart.apply_friction(level);
+    //The player is kart 0 and the computer karts come after
+    fn kart(&self, index: usize) -> &Kart {
+        match index {
+            0 => &self.player_kart,
+            _ => &self.ai_karts[index - 1].kart,
+        }
+    }
 
-        if !self.player_kart.knocked_out() {
-            self.cam
-                .follow(&self.player_kart.sprite, DEFAULT_CAM_FOLLOW_DIST);
+    //Follows the kart that is being watched, which is the player
+    //unless another kart was picked in a replay
+    fn update_camera(&mut self) {
+        self.player_kart.sprite.camera_kart = false;
+        for ai_kart in &mut self.ai_karts {
+            ai_kart.kart.sprite.camera_kart = false;
         }
 
-        if self.player_kart.laps == 4 {
-            self.player_kart.speed = 0.0;
-            self.player_kart.sprite.rotation_speed = 0.0;
+        let kart = match self.watching {
+            0 => &mut self.player_kart,
+            i => &mut self.ai_karts[i - 1].kart,
+        };
+        kart.sprite.camera_kart = !kart.knocked_out();
+        if !kart.knocked_out() {
+            self.cam.follow(&kart.sprite, DEFAULT_CAM_FOLLOW_DIST);
         }
     }
 
+    pub fn kart_count(&self) -> usize {
+        self.ai_karts.len() + 1
+    }
+
+    //Points the camera at another kart, the camera jumps
+    //straight there instead of sweeping across the track
+    pub fn watch_kart(&mut self, index: usize) {
+        self.watching = index % self.kart_count();
+        self.update_camera();
+        self.prev_cam = self.cam;
+        self.camera_switched = true;
+    }
+
+    fn update_positions(&mut self, level: &Level) {
+        //Nobody to be ranked against in a time trial
+        if self.ai_karts.is_empty() {
+            return;
+        }
+
+        let mut karts = vec![&mut self.player_kart];
+        karts.extend(self.ai_karts.iter_mut().map(|ai_kart| &mut ai_kart.kart));
+        race::update_positions(&mut karts, level, self.rules.laps);
+    }
+
+    fn bump_karts(&mut self, level: &Level) {
+        let mut karts = vec![&mut self.player_kart];
+        karts.extend(self.ai_karts.iter_mut().map(|ai_kart| &mut ai_kart.kart));
+        kart::bump_karts(&mut karts, level);
+    }
+
     fn update_enemies(&mut self, dt: f64) {
         for enemy in &mut self.enemies {
-            if dist_between(&enemy.sprite, &self.player_kart.sprite) < 0.2
-                && !self.player_kart.knocked_out()
-            {
-                self.player_kart.knock_out = 1.0;
+            if enemy.stunned() {
+                enemy.update(dt);
+                continue;
+            }
+
+            if dist_between(&enemy.sprite, &self.player_kart.sprite) < 0.2 {
+                self.player_kart.hit(1.0);
+            }
+
+            for ai_kart in &mut self.ai_karts {
+                if dist_between(&enemy.sprite, &ai_kart.kart.sprite) < 0.2 {
+                    ai_kart.kart.hit(1.0);
+                }
             }
 
             enemy.update(dt);
         }
     }
 
-    pub fn update(&mut self, events: &Events, level: &Level, dt: f64) {
-        self.update_kart(events, level, dt);
+    fn use_powerups(&mut self, input: &KartInput) {
+        if input.use_item && self.start_timer <= 0.0 && !self.done() {
+            let mut karts = vec![&mut self.player_kart];
+            karts.extend(self.ai_karts.iter_mut().map(|ai_kart| &mut ai_kart.kart));
+            self.items.use_powerup(&mut karts, 0);
+        }
+    }
+
+    fn update_items(&mut self, level: &Level, dt: f64) {
+        //Computer karts use their items while they are racing
+        let mut users = vec![];
+        for (i, ai_kart) in self.ai_karts.iter_mut().enumerate() {
+            let racing = self.start_timer <= 0.0 && ai_kart.kart.laps < self.rules.laps;
+            if racing && ai_kart.use_item(dt) {
+                users.push(i + 1);
+            }
+        }
+
+        let mut karts = vec![&mut self.player_kart];
+        karts.extend(self.ai_karts.iter_mut().map(|ai_kart| &mut ai_kart.kart));
+        for user in users {
+            self.items.use_powerup(&mut karts, user);
+        }
+        self.items.update(&mut karts, &mut self.enemies, level, dt);
+    }
+
+    fn update_particles(&mut self, dt: f64) {
+        if let Some(particle) = self.player_kart.spawn_particle(dt) {
+            self.particles.push(particle);
+        }
+
+        for ai_kart in &mut self.ai_karts {
+            if let Some(particle) = ai_kart.kart.spawn_particle(dt) {
+                self.particles.push(particle);
+            }
+        }
+
+        for particle in &mut self.particles {
+            particle.update(dt);
+        }
+        self.particles.retain(|particle| !particle.done());
+    }
+
+    fn update_ghost(&mut self, level: &Level) {
+        if let Some(ghost) = &mut self.ghost {
+            ghost.update(self.timer);
+        }
+
+        if !self.done() {
+            //Record the run once the countdown is over
+            if let Some(recorder) = &mut self.recorder {
+                if self.start_timer < 0.0 {
+                    recorder.record(self.timer, &self.player_kart.sprite);
+                }
+            }
+            return;
+        }
+
+        //Save the run if it beat the ghost
+        if let Some(recorder) = self.recorder.take() {
+            let best = self.ghost.as_ref().map(|ghost| ghost.time);
+            if best.is_none_or(|best| self.race_time() < best) {
+                self.new_best = true;
+                let path = ghost::ghost_path(level, self.rules.laps);
+                if let Err(msg) = recorder.save(&path, self.race_time()) {
+                    eprintln!("{msg}");
+                    eprintln!("Cannot save ghost: {path}");
+                }
+            }
+        }
+    }
+
+    //Remembers where everything is at the start of a tick
+    //so that drawing can blend between ticks
+    fn save_positions(&mut self) {
+        self.prev_cam = self.cam;
+        self.player_kart.sprite.save_position();
+        self.checkpoint.save_position();
+        for ai_kart in &mut self.ai_karts {
+            ai_kart.kart.sprite.save_position();
+        }
+        for enemy in &mut self.enemies {
+            enemy.sprite.save_position();
+        }
+        for particle in &mut self.particles {
+            particle.sprite.save_position();
+        }
+        if let Some(ghost) = &mut self.ghost {
+            ghost.sprite.save_position();
+        }
+        self.items.save_positions();
+    }
+
+    //Sets how far (0 to 1) between the last two ticks everything is drawn
+    pub fn set_interpolation(&mut self, t: f64) {
+        self.interpolation = t;
+    }
+
+    //Runs one tick of the race
+    pub fn update(&mut self, input: &KartInput, level: &Level, dt: f64) {
+        self.save_positions();
+        self.use_powerups(input);
+        self.update_kart(input, level, dt);
+        self.update_ai_karts(level, dt);
+        self.update_camera();
+        self.bump_karts(level);
+        self.update_positions(level);
         self.update_enemies(dt);
+        self.update_items(level, dt);
+        self.update_particles(dt);
 
         if self.start_timer > -1.0 {
             self.start_timer -= dt;
         }
 
-        if self.player_kart.laps < 4 && self.start_timer < 0.0 {
+        //Keeps running after the player finishes so the computer karts are timed
+        if self.start_timer < 0.0 {
             self.timer += dt;
         }
+
+        if self.done() {
+            self.finish_timer += dt;
+        }
+
+        self.update_ghost(level);
+    }
+
+    //Positions of the player followed by the computer karts
+    pub fn positions(&self) -> Vec<usize> {
+        let mut positions = vec![self.player_kart.position];
+        positions.extend(self.ai_karts.iter().map(|ai_kart| ai_kart.kart.position));
+        positions
+    }
+
+    pub fn racer_names(&self) -> &[String] {
+        &self.racer_names
     }
 
     pub fn done(&self) -> bool {
-        self.player_kart.laps >= 4
+        self.player_kart.laps >= self.rules.laps
+    }
+
+    //Returns true once the player has had a few seconds
+    //to see where they finished
+    pub fn results_ready(&self) -> bool {
+        self.finish_timer > RESULTS_DELAY
+    }
+
+    //How long the player has been racing for, stops when they finish
+    pub fn race_time(&self) -> f64 {
+        if self.done() {
+            self.player_kart.lap_timer.last_lap_finished()
+        } else {
+            self.timer
+        }
+    }
+
+    //How every kart did in the race, the player is first
+    //followed by the computer karts
+    pub fn results(&self) -> Vec<RacerResult> {
+        let karts = std::iter::once(&self.player_kart)
+            .chain(self.ai_karts.iter().map(|ai_kart| &ai_kart.kart));
+        self.racer_names
+            .iter()
+            .zip(karts)
+            .map(|(name, kart)| RacerResult::new(name, kart, self.rules.laps))
+            .collect()
     }
 }
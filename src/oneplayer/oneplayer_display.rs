@@ -1,8 +1,8 @@
-use super::{SingeplayerState, START_TIMER};
+use super::SingeplayerState;
 use crate::display;
-use crate::level::Level;
+use crate::level::{Camera, Level};
 use crate::menu::Text;
-use crate::sprite::{bitmap::BitMap, Sprite, SpriteType};
+use crate::sprite::{Sprite, SpriteType};
 use crate::{HEIGHT, WIDTH};
 use sdl2::pixels::Color;
 use sdl2::rect::{Point, Rect};
@@ -13,15 +13,19 @@ use std::collections::HashMap;
 
 impl SingeplayerState {
     pub fn create_background_texture(
-        &self,
+        &mut self,
         pixel_buffer: &mut [u8],
         level: &Level,
-        track_textures: &HashMap<u32, BitMap>,
         background_texture: &mut Texture,
     ) -> Result<(), String> {
-        //Display Level
-        if self.player_kart.moving() || self.start_timer == START_TIMER {
-            level.display_level(pixel_buffer, WIDTH, HEIGHT / 2, &self.cam, track_textures);
+        //Display Level, it is always drawn in the first second of the countdown
+        //since the race can tick a few times before the first frame is shown
+        if self.kart(self.watching).moving()
+            || self.start_timer > self.rules.countdown - 1.0
+            || self.camera_switched
+        {
+            level.display_level(pixel_buffer, WIDTH, HEIGHT / 2, &self.view_cam());
+            self.camera_switched = false;
         }
 
         background_texture
@@ -31,6 +35,11 @@ impl SingeplayerState {
         Ok(())
     }
 
+    //The camera part of the way between the last two ticks
+    fn view_cam(&self) -> Camera {
+        self.cam.interpolated(&self.prev_cam, self.interpolation)
+    }
+
     fn display_timer(
         &self,
         canvas: &mut Canvas<Window>,
@@ -42,8 +51,8 @@ impl SingeplayerState {
         timer_text.x = canv_w as i32 / 2;
         timer_text.y = (canv_h as i32) / 8 - 32;
         //Display timer
-        let minutes = (self.timer / 60.0).floor();
-        let seconds = (self.timer - 60.0 * minutes).floor();
+        let minutes = (self.race_time() / 60.0).floor();
+        let seconds = (self.race_time() - 60.0 * minutes).floor();
         if seconds < 10.0 {
             timer_text.text = format!("{}:0{}", minutes, seconds);
         } else {
@@ -51,6 +60,19 @@ impl SingeplayerState {
         }
 
         timer_text.display_center(canvas, texture_creator, font)?;
+
+        //Display the time to beat in a time trial
+        if let Some(ghost) = &self.ghost {
+            let best_text = Text::new(
+                format!("best: {}", display::format_time(ghost.time)).as_str(),
+                canv_w as i32 / 2,
+                timer_text.y + 64,
+                Color::WHITE,
+                16,
+            );
+            best_text.display_center(canvas, texture_creator, font)?;
+        }
+
         Ok(())
     }
 
@@ -84,16 +106,35 @@ impl SingeplayerState {
         canvas: &mut Canvas<Window>,
         texture_creator: &TextureCreator<WindowContext>,
         font: &Font,
+        powerup_icons: &[Texture],
     ) -> Result<(), String> {
         let canvas_dimensions = canvas.output_size()?;
 
         //Display the timer
         self.display_timer(canvas, texture_creator, font)?;
 
-        //Display player info
-        display::display_player_info(canvas, texture_creator, font, &self.player_kart, 0, 0)?;
+        //Display info about the kart the camera is following
+        let kart = self.kart(self.watching);
+        //Computer karts can still be racing after the player's time stops
+        let time = if self.watching == 0 {
+            self.race_time()
+        } else {
+            self.timer
+        };
+        display::display_player_info(canvas, texture_creator, font, kart, 0, 0)?;
+        display::display_lap_times(canvas, texture_creator, font, kart, time, 0)?;
 
-        //Print DONE when player reaches 4 laps
+        //Display the items the kart is holding under the timer
+        display::display_powerup_icons(
+            canvas,
+            powerup_icons,
+            48,
+            kart,
+            canvas_dimensions.0 as i32 / 2,
+            canvas_dimensions.1 as i32 / 4,
+        )?;
+
+        //Print DONE when player finishes the race
         if self.done() {
             let (canv_w, canv_h) = canvas_dimensions;
             let victory_text = Text::new(
@@ -104,6 +145,17 @@ impl SingeplayerState {
                 32,
             );
             victory_text.display_center(canvas, texture_creator, font)?;
+
+            if self.new_best {
+                let best_text = Text::new(
+                    "NEW BEST TIME!",
+                    canv_w as i32 / 2,
+                    canv_h as i32 / 2 + 32,
+                    Color::RGB(255, 128, 0),
+                    24,
+                );
+                best_text.display_center(canvas, texture_creator, font)?;
+            }
         }
 
         display::display_start_timer(
@@ -117,20 +169,40 @@ impl SingeplayerState {
         Ok(())
     }
 
-    fn get_sprites_to_draw(&self) -> Vec<&Sprite> {
+    //Copies of the sprites placed between the last two ticks
+    fn get_sprites_to_draw(&self, cam: &Camera) -> Vec<Sprite> {
         let mut sprites_to_draw = vec![];
         sprites_to_draw.push(&self.player_kart.sprite);
         sprites_to_draw.push(&self.checkpoint);
 
+        for ai_kart in &self.ai_karts {
+            sprites_to_draw.push(&ai_kart.kart.sprite);
+        }
+
+        if let Some(ghost) = &self.ghost {
+            sprites_to_draw.push(&ghost.sprite);
+        }
+
+        sprites_to_draw.extend(self.items.sprites());
+
         for enemy in &self.enemies {
             sprites_to_draw.push(&enemy.sprite);
         }
 
+        for particle in &self.particles {
+            sprites_to_draw.push(&particle.sprite);
+        }
+
+        let mut sprites_to_draw: Vec<Sprite> = sprites_to_draw
+            .iter()
+            .map(|sprite| sprite.interpolated(self.interpolation))
+            .collect();
+
         //Sort sprite vector based on distance to camera
         sprites_to_draw.sort_by(|sprite1, sprite2| {
             sprite2
-                .dist2_to_camera(&self.cam)
-                .partial_cmp(&sprite1.dist2_to_camera(&self.cam))
+                .dist2_to_camera(cam)
+                .partial_cmp(&sprite1.dist2_to_camera(cam))
                 .unwrap()
         });
 
@@ -140,7 +212,7 @@ impl SingeplayerState {
     pub fn display_sprites(
         &self,
         canvas: &mut Canvas<Window>,
-        sprite_assets: &HashMap<SpriteType, Texture>,
+        sprite_assets: &mut HashMap<SpriteType, Texture>,
     ) -> Result<(), String> {
         let canvas_dimensions = canvas.output_size()?;
         let canvas_dimensions_half = (canvas_dimensions.0, canvas_dimensions.1 / 2);
@@ -156,7 +228,9 @@ impl SingeplayerState {
         );
 
         let origin_y = texture_rect.y() / 2;
-        let sprites_to_draw = self.get_sprites_to_draw();
+        let cam = self.view_cam();
+        let sprites = self.get_sprites_to_draw(&cam);
+        let sprites_to_draw: Vec<&Sprite> = sprites.iter().collect();
 
         let offset_y = if display::cmp_aspect(canvas_dimensions_half, WIDTH, HEIGHT / 2) {
             0
@@ -166,7 +240,7 @@ impl SingeplayerState {
 
         display::display_sprites(
             canvas,
-            &self.cam,
+            &cam,
             &sprites_to_draw,
             canvas_dimensions_half,
             (0, canvas_dimensions_half.1 as i32 + offset_y),
@@ -0,0 +1,202 @@
+use crate::item::registry::ItemOdds;
+use crate::level::Level;
+use crate::sprite::kart::Kart;
+use std::cmp::Ordering;
+
+//Length of the countdown before a race starts (in seconds)
+pub const DEFAULT_COUNTDOWN: f64 = 3.0;
+pub const MAX_COUNTDOWN: f64 = 5.0;
+pub const MAX_LAPS: u32 = 10;
+//How long the difference to the best lap is shown after reaching a checkpoint
+const SPLIT_DISPLAY_TIME: f64 = 3.0;
+
+//Settings for a race that are picked before it starts
+#[derive(Clone, Copy)]
+pub struct RaceRules {
+    pub laps: u32,       //Laps needed to finish the race
+    pub countdown: f64,  //Seconds before the karts can start driving
+    pub items: ItemOdds, //Which items come out of the powerup boxes
+    pub enemies: bool,   //Are there enemies patrolling the track
+    pub seed: u64, //Seed for everything random in the race, the same seed gives the same rolls
+}
+
+impl RaceRules {
+    //The default rules for racing on a track
+    pub fn new(level: &Level) -> Self {
+        Self {
+            laps: level.laps.clamp(1, MAX_LAPS),
+            countdown: DEFAULT_COUNTDOWN,
+            items: ItemOdds::Balanced,
+            enemies: true,
+            seed: 0,
+        }
+    }
+
+    //Goes up by one lap, going back to 1 after MAX_LAPS
+    pub fn next_lap_count(&mut self) {
+        self.laps = self.laps % MAX_LAPS + 1;
+    }
+
+    //Goes up by one second, going back to 1 after MAX_COUNTDOWN
+    pub fn next_countdown(&mut self) {
+        self.countdown = self.countdown.round() % MAX_COUNTDOWN + 1.0;
+    }
+}
+
+//Keeps track of how long each lap of a kart takes and the time
+//into the lap that each checkpoint was reached (splits)
+pub struct LapTimer {
+    pub lap_times: Vec<f64>,
+    lap_start: f64,
+    splits: Vec<f64>,
+    best_splits: Vec<f64>,    //Splits of the fastest lap
+    split_delta: Option<f64>, //Difference to the best lap at the last checkpoint
+    split_time: f64,          //Race time that the last checkpoint was reached
+}
+
+impl LapTimer {
+    pub fn new() -> Self {
+        Self {
+            lap_times: vec![],
+            lap_start: 0.0,
+            splits: vec![],
+            best_splits: vec![],
+            split_delta: None,
+            split_time: 0.0,
+        }
+    }
+
+    //Records the race time that a checkpoint was reached,
+    //lap_finished is true if it was the last checkpoint of the lap
+    pub fn checkpoint(&mut self, time: f64, lap_finished: bool) {
+        let lap_time = time - self.lap_start;
+        self.split_delta = self
+            .best_splits
+            .get(self.splits.len())
+            .map(|best| lap_time - best);
+        self.split_time = time;
+        self.splits.push(lap_time);
+
+        if !lap_finished {
+            return;
+        }
+
+        if self.best_lap().is_none_or(|best| lap_time < best) {
+            self.best_splits = std::mem::take(&mut self.splits);
+        }
+        self.splits.clear();
+        self.lap_times.push(lap_time);
+        self.lap_start = time;
+    }
+
+    //Race time that the last lap was finished,
+    //this is the kart's finishing time once it has done every lap
+    pub fn last_lap_finished(&self) -> f64 {
+        self.lap_start
+    }
+
+    pub fn current_lap_time(&self, time: f64) -> f64 {
+        time - self.lap_start
+    }
+
+    pub fn last_lap(&self) -> Option<f64> {
+        self.lap_times.last().copied()
+    }
+
+    pub fn best_lap(&self) -> Option<f64> {
+        self.lap_times.iter().copied().reduce(f64::min)
+    }
+
+    //Difference to the best lap at the last checkpoint (negative if it was faster),
+    //only returned for a few seconds after the checkpoint
+    pub fn split_delta(&self, time: f64) -> Option<f64> {
+        self.split_delta
+            .filter(|_| time - self.split_time < SPLIT_DISPLAY_TIME)
+    }
+}
+
+//How a kart did in a race, shown on the results screen
+pub struct RacerResult {
+    pub name: String,
+    pub position: usize,
+    pub finish_time: Option<f64>, //None if the kart didn't finish before the race ended
+    pub lap_times: Vec<f64>,
+    pub items_used: u32,
+    pub knockouts: u32, //How many times the kart was knocked out
+}
+
+impl RacerResult {
+    pub fn new(name: &str, kart: &Kart, laps_to_win: u32) -> Self {
+        Self {
+            name: String::from(name),
+            //A kart racing alone is never ranked
+            position: kart.position.max(1),
+            finish_time: if kart.laps >= laps_to_win {
+                Some(kart.lap_timer.last_lap_finished())
+            } else {
+                None
+            },
+            lap_times: kart.lap_timer.lap_times.clone(),
+            items_used: kart.items_used,
+            knockouts: kart.knockouts,
+        }
+    }
+
+    pub fn best_lap(&self) -> Option<f64> {
+        self.lap_times.iter().copied().reduce(f64::min)
+    }
+}
+
+//Returns the position as text (1st, 2nd, 3rd, 4th, ...)
+pub fn ordinal(position: usize) -> String {
+    let suffix = match (position % 10, position % 100) {
+        (_, 11..=13) => "th",
+        (1, _) => "st",
+        (2, _) => "nd",
+        (3, _) => "rd",
+        _ => "th",
+    };
+
+    format!("{position}{suffix}")
+}
+
+fn dist_to_next_checkpoint(kart: &Kart, level: &Level) -> f64 {
+    let (x, z) = level.checkpoint_position(kart.current_checkpoint);
+    let xdiff = x - kart.sprite.trans_x;
+    let zdiff = z - kart.sprite.trans_z;
+    (xdiff * xdiff + zdiff * zdiff).sqrt()
+}
+
+//Compares how far along the race two karts are, returns Greater if
+//kart1 is ahead of kart2
+fn compare_progress(kart1: &Kart, kart2: &Kart, level: &Level, laps_to_win: u32) -> Ordering {
+    let finished1 = kart1.laps >= laps_to_win;
+    let finished2 = kart2.laps >= laps_to_win;
+
+    //Karts that finished stay in the order that they crossed the line
+    if finished1 && finished2 {
+        return kart2.position.cmp(&kart1.position);
+    }
+
+    finished1
+        .cmp(&finished2)
+        .then(kart1.laps.cmp(&kart2.laps))
+        .then(kart1.current_checkpoint.cmp(&kart2.current_checkpoint))
+        .then_with(|| {
+            let dist1 = dist_to_next_checkpoint(kart1, level);
+            let dist2 = dist_to_next_checkpoint(kart2, level);
+            dist2.partial_cmp(&dist1).unwrap_or(Ordering::Equal)
+        })
+}
+
+//Ranks the karts based on their laps, their next checkpoint and
+//how close they are to it and sets the position (1st = 1) of every kart,
+//this should be called every frame so that finishing order is kept
+pub fn update_positions(karts: &mut [&mut Kart], level: &Level, laps_to_win: u32) {
+    let mut order: Vec<usize> = (0..karts.len()).collect();
+    order.sort_by(|&a, &b| compare_progress(karts[b], karts[a], level, laps_to_win));
+
+    for (i, index) in order.into_iter().enumerate() {
+        karts[index].position = i + 1;
+    }
+}
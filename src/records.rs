@@ -0,0 +1,281 @@
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+pub const RECORDS_PATH: &str = "saves/records.txt";
+//How many records are kept in each table
+pub const MAX_RECORDS: usize = 5;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum RecordType {
+    Race, //Fastest time to finish a race with a certain number of laps
+    Lap,  //Fastest single lap
+}
+
+pub struct Record {
+    pub record_type: RecordType,
+    pub track: String, //id of the track
+    pub laps: u32,     //Laps in the race that the record was set in
+    pub time: f64,
+    pub date: String,
+    pub name: String,
+}
+
+/*
+ * Records are stored in a text file with one record on each line:
+ *   <race or lap> <track id> <laps> <time> <date> <name>
+ * the name is last so that it can have spaces in it, spaces in the track id
+ * are written as \s (and backslashes as \\) since track ids are file names
+ * */
+
+pub struct Records {
+    records: Vec<Record>,
+    //Lines that couldn't be read, they are written back
+    //when saving so that fixing the file gets them back
+    unreadable: Vec<String>,
+    //False if the file couldn't be opened, it isn't saved over
+    //so that the records in it aren't lost
+    loaded: bool,
+}
+
+fn escape_track(track: &str) -> String {
+    track.replace('\\', "\\\\").replace(' ', "\\s")
+}
+
+fn unescape_track(track: &str) -> String {
+    let mut unescaped = String::new();
+    let mut chars = track.chars().peekable();
+    while let Some(c) = chars.next() {
+        match (c, chars.peek()) {
+            ('\\', Some('s')) => {
+                unescaped.push(' ');
+                chars.next();
+            }
+            ('\\', Some('\\')) => {
+                unescaped.push('\\');
+                chars.next();
+            }
+            _ => unescaped.push(c),
+        }
+    }
+    unescaped
+}
+
+//Returns today's date as year-month-day
+pub fn today() -> String {
+    let secs = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    //Convert days since 1970-01-01 to a date in the (proleptic) Gregorian calendar
+    let days = (secs / 86400) as i64 + 719468;
+    let era = days.div_euclid(146097);
+    let day_of_era = days.rem_euclid(146097);
+    let year_of_era =
+        (day_of_era - day_of_era / 1460 + day_of_era / 36524 - day_of_era / 146096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let mp = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = year_of_era + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!("{year}-{month:02}-{day:02}")
+}
+
+fn parse_record(line: &str) -> Result<Record, String> {
+    let mut values = line.splitn(6, ' ');
+    let mut next = |what: &str| values.next().ok_or(format!("missing {what}"));
+
+    let record_type = match next("record type")? {
+        "race" => RecordType::Race,
+        "lap" => RecordType::Lap,
+        unknown => return Err(format!("unknown record type `{unknown}`")),
+    };
+    let track = unescape_track(next("track")?);
+    let laps = next("laps")?.parse::<u32>().map_err(|e| e.to_string())?;
+    let time = next("time")?.parse::<f64>().map_err(|e| e.to_string())?;
+    let date = String::from(next("date")?);
+    let name = String::from(next("name")?);
+
+    Ok(Record {
+        record_type,
+        track,
+        laps,
+        time,
+        date,
+        name,
+    })
+}
+
+impl Records {
+    pub fn new() -> Self {
+        Self {
+            records: vec![],
+            unreadable: vec![],
+            loaded: true,
+        }
+    }
+
+    //Used when the records file couldn't be opened, the records set
+    //are shown but they aren't saved over the file
+    pub fn not_loaded() -> Self {
+        Self {
+            loaded: false,
+            ..Self::new()
+        }
+    }
+
+    //Reads every line that can be read, the lines
+    //that can't be are kept as they are
+    fn parse(src: &str, path: &str) -> Records {
+        let mut records = Records::new();
+        for (i, line) in src.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            match parse_record(line) {
+                Ok(record) => records.records.push(record),
+                Err(e) => {
+                    eprintln!("{path}: line {}: {e}", i + 1);
+                    records.unreadable.push(String::from(line));
+                }
+            }
+        }
+        records
+    }
+
+    pub fn load(path: &str) -> Result<Records, String> {
+        let src = match fs::read_to_string(path) {
+            Ok(src) => src,
+            //Nobody has set a record yet
+            Err(e) if e.kind() == ErrorKind::NotFound => String::new(),
+            Err(e) => {
+                eprintln!("{e}");
+                eprintln!("Cannot open records: {path}");
+                return Err(String::from("Failed to open records"));
+            }
+        };
+
+        Ok(Self::parse(&src, path))
+    }
+
+    fn to_src(&self) -> String {
+        let mut src = String::new();
+        for record in &self.records {
+            let record_type = match record.record_type {
+                RecordType::Race => "race",
+                RecordType::Lap => "lap",
+            };
+            src.push_str(&format!(
+                "{record_type} {} {} {} {} {}\n",
+                escape_track(&record.track),
+                record.laps,
+                record.time,
+                record.date,
+                record.name
+            ));
+        }
+        for line in &self.unreadable {
+            src.push_str(&format!("{line}\n"));
+        }
+        src
+    }
+
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        if !self.loaded {
+            return Err(format!("{path}: was not loaded, not saving over it"));
+        }
+        let src = self.to_src();
+
+        if let Some(dir) = Path::new(path).parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+        }
+        fs::write(path, src).map_err(|e| format!("{path}: {e}"))
+    }
+
+    //Returns the records for a track from fastest to slowest, race records
+    //are only compared with races that had the same number of laps
+    pub fn table(&self, record_type: RecordType, track: &str, laps: u32) -> Vec<&Record> {
+        let mut table: Vec<&Record> = self
+            .records
+            .iter()
+            .filter(|record| record.record_type == record_type && record.track == track)
+            .filter(|record| record_type == RecordType::Lap || record.laps == laps)
+            .collect();
+        table.sort_by(|a, b| a.time.total_cmp(&b.time));
+        table.truncate(MAX_RECORDS);
+        table
+    }
+
+    //Returns true if the time is fast enough to get into the table
+    pub fn qualifies(&self, record_type: RecordType, track: &str, laps: u32, time: f64) -> bool {
+        let table = self.table(record_type, track, laps);
+        table.len() < MAX_RECORDS || table.iter().any(|record| time < record.time)
+    }
+
+    //Adds a record, dropping the slowest one if the table is full
+    pub fn add(&mut self, record: Record) {
+        let (record_type, track, laps) = (record.record_type, record.track.clone(), record.laps);
+        self.records.push(record);
+
+        let table = self.table(record_type, &track, laps);
+        if table.len() < MAX_RECORDS {
+            return;
+        }
+        let slowest = table[table.len() - 1].time;
+        let in_table = |r: &Record| {
+            r.record_type == record_type
+                && r.track == track
+                && (record_type == RecordType::Lap || r.laps == laps)
+        };
+        //Anything slower than the last record in the table is dropped
+        self.records.retain(|r| !in_table(r) || r.time <= slowest);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(track: &str) -> Record {
+        Record {
+            record_type: RecordType::Race,
+            track: String::from(track),
+            laps: 3,
+            time: 61.25,
+            date: String::from("2024-01-02"),
+            name: String::from("Ferris the Crab"),
+        }
+    }
+
+    #[test]
+    fn track_ids_with_spaces_are_read_back() {
+        let mut records = Records::new();
+        records.add(record("my track"));
+        records.add(record(r"odd\s name\"));
+
+        let loaded = Records::parse(&records.to_src(), "test");
+        assert!(loaded.unreadable.is_empty());
+        assert_eq!(loaded.table(RecordType::Race, "my track", 3).len(), 1);
+        let odd = loaded.table(RecordType::Race, r"odd\s name\", 3);
+        assert_eq!(odd.len(), 1);
+        assert_eq!(odd[0].name, "Ferris the Crab");
+    }
+
+    #[test]
+    fn unreadable_lines_are_kept() {
+        let src = "race crab-circuit 3 61.25 2024-01-02 Ferris\nnot a record\n";
+        let records = Records::parse(src, "test");
+        assert_eq!(records.table(RecordType::Race, "crab-circuit", 3).len(), 1);
+        assert_eq!(records.unreadable, vec![String::from("not a record")]);
+        assert!(records.to_src().contains("not a record\n"));
+    }
+
+    #[test]
+    fn records_that_were_not_loaded_are_not_saved() {
+        assert!(Records::not_loaded()
+            .save("/nonexistent/records.txt")
+            .is_err());
+    }
+}
@@ -0,0 +1,173 @@
+use sdl2::{
+    keyboard::Keycode,
+    pixels::Color,
+    render::{Canvas, TextureCreator},
+    ttf::Font,
+    video::{Window, WindowContext},
+};
+
+use crate::{
+    display,
+    events::Events,
+    level::Level,
+    menu::{Button, Text},
+    race::{self, RaceRules},
+    records::{Record, RecordType, Records},
+};
+
+//Shows the fastest races and laps that have been set on each track
+pub struct RecordsScreen {
+    selected: usize,
+    laps: u32, //Race records are shown for races with this many laps
+    prev_button: Button,
+    next_button: Button,
+    laps_button: Button,
+    back_button: Button,
+}
+
+impl RecordsScreen {
+    pub fn init(tracks: &[Level]) -> Self {
+        let mut records_screen = Self {
+            selected: 0,
+            laps: 0,
+            prev_button: Button::new(-320, -176, "<"),
+            next_button: Button::new(320, -176, ">"),
+            laps_button: Button::new(0, -120, ""),
+            back_button: Button::new(0, 232, "Back"),
+        };
+        records_screen.select_track(0, tracks);
+        records_screen
+    }
+
+    fn select_track(&mut self, index: usize, tracks: &[Level]) {
+        self.selected = index;
+        self.set_laps(RaceRules::new(&tracks[index]).laps);
+    }
+
+    fn set_laps(&mut self, laps: u32) {
+        self.laps = laps;
+        self.laps_button.text = format!("Laps: {laps}");
+    }
+
+    //Returns true if the player wants to go back to the main menu
+    pub fn press_buttons(
+        &mut self,
+        events: &mut Events,
+        canvas_dimensions: (u32, u32),
+        tracks: &[Level],
+    ) -> bool {
+        let track_count = tracks.len();
+
+        if self.prev_button.clicked(events, canvas_dimensions)
+            || events.key_is_pressed_once(Keycode::Left)
+        {
+            self.select_track((self.selected + track_count - 1) % track_count, tracks);
+        } else if self.next_button.clicked(events, canvas_dimensions)
+            || events.key_is_pressed_once(Keycode::Right)
+        {
+            self.select_track((self.selected + 1) % track_count, tracks);
+        } else if self.laps_button.clicked(events, canvas_dimensions) {
+            self.set_laps(self.laps % race::MAX_LAPS + 1);
+        } else if self.back_button.clicked(events, canvas_dimensions)
+            || events.key_is_pressed_once(Keycode::Escape)
+        {
+            return true;
+        }
+
+        false
+    }
+
+    fn display_table(
+        canvas: &mut Canvas<Window>,
+        texture_creator: &TextureCreator<WindowContext>,
+        font: &Font,
+        table: &[&Record],
+        title: &str,
+        y: i32,
+    ) -> Result<(), String> {
+        let (canv_w, _) = canvas.output_size()?;
+        let center_x = canv_w as i32 / 2;
+
+        let title_text = Text::new(title, center_x, y, Color::RGB(255, 128, 0), 16);
+        title_text.display_center(canvas, texture_creator, font)?;
+
+        if table.is_empty() {
+            let empty_text = Text::new("No records yet", center_x, y + 24, Color::WHITE, 16);
+            empty_text.display_center(canvas, texture_creator, font)?;
+        }
+
+        for (i, record) in table.iter().enumerate() {
+            let row_text = Text::new(
+                format!(
+                    "{}. {:<12} {} {}",
+                    i + 1,
+                    record.name,
+                    display::format_time(record.time),
+                    record.date
+                )
+                .as_str(),
+                center_x,
+                y + 24 * (i as i32 + 1),
+                Color::WHITE,
+                16,
+            );
+            row_text.display_center(canvas, texture_creator, font)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn display(
+        &self,
+        canvas: &mut Canvas<Window>,
+        texture_creator: &TextureCreator<WindowContext>,
+        events: &Events,
+        font: &Font,
+        tracks: &[Level],
+        records: &Records,
+    ) -> Result<(), String> {
+        let (canv_w, canv_h) = canvas.output_size()?;
+        let center_y = canv_h as i32 / 2;
+        let track = &tracks[self.selected];
+
+        let title_text = Text::new("Records", canv_w as i32 / 2, 16, Color::WHITE, 32);
+        title_text.display_center(canvas, texture_creator, font)?;
+
+        let name_text = Text::new(
+            track.name.as_str(),
+            canv_w as i32 / 2,
+            center_y - 200,
+            Color::WHITE,
+            24,
+        );
+        name_text.display_center(canvas, texture_creator, font)?;
+
+        Self::display_table(
+            canvas,
+            texture_creator,
+            font,
+            &records.table(RecordType::Race, &track.id, self.laps),
+            "Fastest races",
+            center_y - 88,
+        )?;
+        Self::display_table(
+            canvas,
+            texture_creator,
+            font,
+            &records.table(RecordType::Lap, &track.id, self.laps),
+            "Fastest laps",
+            center_y + 56,
+        )?;
+
+        self.prev_button
+            .display(canvas, texture_creator, events, font)?;
+        self.next_button
+            .display(canvas, texture_creator, events, font)?;
+        self.laps_button
+            .display(canvas, texture_creator, events, font)?;
+        self.back_button
+            .display(canvas, texture_creator, events, font)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,488 @@
+use crate::character::Character;
+use crate::item::registry::ItemOdds;
+use crate::level::Level;
+use crate::race::{self, RaceRules};
+use crate::records;
+use crate::sprite::{ai_kart::Difficulty, kart::KartInput, load_assets::KART_SLOTS};
+use crate::GameScreen;
+use std::fs;
+use std::path::Path;
+
+/*
+ * Replay files are text. Each line is `key = value`, the first lines
+ * describe the race:
+ *
+ *   mode = <one-player, time-trial or two-player>
+ *   track = <id of the track>
+ *   laps, countdown, items, enemies, seed and difficulty = <race rules>
+ *   racer = <name of a kart> (one line for each racer, the players come first)
+ *
+ * and they are followed by an `inputs` line for each player with the
+ * buttons held on every tick. The buttons of a tick are a number where each
+ * bit is a button (accelerate, brake, drift, left, right, use item) and a run
+ * of ticks with the same buttons is written once as <ticks>x<buttons>,
+ * everything else in the race is simulated again from the seed
+ * */
+
+const REPLAY_DIR: &str = "saves/replays";
+//An hour of racing, longer replays are cut off when recording and refused when loading
+const MAX_TICKS: usize = 60 * 60 * 120;
+const MODES: [(GameScreen, &str); 3] = [
+    (GameScreen::OnePlayer, "one-player"),
+    (GameScreen::TimeTrial, "time-trial"),
+    (GameScreen::TwoPlayer, "two-player"),
+];
+
+fn input_bits(input: &KartInput) -> u8 {
+    [
+        input.accelerate,
+        input.brake,
+        input.drift,
+        input.left,
+        input.right,
+        input.use_item,
+    ]
+    .iter()
+    .enumerate()
+    .map(|(i, &pressed)| (pressed as u8) << i)
+    .sum()
+}
+
+fn input_from_bits(bits: u8) -> KartInput {
+    let pressed = |i: u8| bits & (1 << i) != 0;
+    KartInput {
+        accelerate: pressed(0),
+        brake: pressed(1),
+        drift: pressed(2),
+        left: pressed(3),
+        right: pressed(4),
+        use_item: pressed(5),
+    }
+}
+
+fn encode_inputs(inputs: &[KartInput]) -> String {
+    let mut runs: Vec<(usize, u8)> = vec![];
+    for input in inputs {
+        let bits = input_bits(input);
+        match runs.last_mut() {
+            Some((ticks, last)) if *last == bits => *ticks += 1,
+            _ => runs.push((1, bits)),
+        }
+    }
+
+    runs.iter()
+        .map(|(ticks, bits)| format!("{ticks}x{bits}"))
+        .collect::<Vec<String>>()
+        .join(" ")
+}
+
+fn decode_inputs(src: &str) -> Result<Vec<KartInput>, String> {
+    let mut inputs = vec![];
+    for run in src.split_whitespace() {
+        let (ticks, bits) = run
+            .split_once('x')
+            .ok_or(format!("{run}: expected <ticks>x<buttons>"))?;
+        let ticks = ticks.parse::<usize>().map_err(|e| format!("{run}: {e}"))?;
+        let bits = bits.parse::<u8>().map_err(|e| format!("{run}: {e}"))?;
+        let total = inputs
+            .len()
+            .checked_add(ticks)
+            .filter(|&total| total <= MAX_TICKS)
+            .ok_or(format!("{run}: replay is longer than {MAX_TICKS} ticks"))?;
+        inputs.resize(total, input_from_bits(bits));
+    }
+
+    Ok(inputs)
+}
+
+//Everything needed to play a race again: the rules it was
+//raced with and the controls of each player on every tick
+#[derive(Clone)]
+pub struct Replay {
+    pub mode: GameScreen,
+    pub track: String, //id of the track
+    pub rules: RaceRules,
+    pub difficulty: Difficulty,
+    pub racers: Vec<String>,     //Names of the karts, the players come first
+    inputs: Vec<Vec<KartInput>>, //Inputs of each player for every tick
+}
+
+impl Replay {
+    //Starts recording a race, a grand prix race is replayed as a single race
+    pub fn new(
+        mode: GameScreen,
+        level: &Level,
+        rules: RaceRules,
+        difficulty: Difficulty,
+        racers: &[&Character],
+    ) -> Self {
+        let mode = match mode {
+            GameScreen::GrandPrix => GameScreen::OnePlayer,
+            mode => mode,
+        };
+
+        Self {
+            mode,
+            track: level.id.clone(),
+            rules,
+            difficulty,
+            racers: racers.iter().map(|racer| racer.name.clone()).collect(),
+            inputs: vec![vec![]; Self::player_count(mode)],
+        }
+    }
+
+    fn player_count(mode: GameScreen) -> usize {
+        if mode == GameScreen::TwoPlayer {
+            2
+        } else {
+            1
+        }
+    }
+
+    //A one player race needs a kart for every slot or the replay
+    //would be raced again with fewer computer karts
+    fn racer_count(mode: GameScreen) -> usize {
+        if mode == GameScreen::OnePlayer {
+            KART_SLOTS.len()
+        } else {
+            Self::player_count(mode)
+        }
+    }
+
+    //Adds a tick with the inputs of each player
+    pub fn record(&mut self, inputs: &[KartInput]) {
+        for (player, input) in self.inputs.iter_mut().zip(inputs) {
+            if player.len() < MAX_TICKS {
+                player.push(*input);
+            }
+        }
+    }
+
+    //Number of ticks that were recorded
+    pub fn ticks(&self) -> usize {
+        self.inputs
+            .iter()
+            .map(|inputs| inputs.len())
+            .max()
+            .unwrap_or(0)
+    }
+
+    //The player lets go of every button after the recording ends
+    pub fn input(&self, player: usize, tick: usize) -> KartInput {
+        self.inputs
+            .get(player)
+            .and_then(|inputs| inputs.get(tick))
+            .copied()
+            .unwrap_or_default()
+    }
+
+    //Finds the karts of the racers by name
+    pub fn racers<'a>(&self, characters: &'a [Character]) -> Result<Vec<&'a Character>, String> {
+        self.racers
+            .iter()
+            .map(|name| {
+                characters
+                    .iter()
+                    .find(|character| &character.name == name)
+                    .ok_or(format!("unknown kart `{name}`"))
+            })
+            .collect()
+    }
+
+    pub fn track_index(&self, tracks: &[Level]) -> Result<usize, String> {
+        tracks
+            .iter()
+            .position(|level| level.id == self.track)
+            .ok_or(format!("unknown track `{}`", self.track))
+    }
+
+    fn to_src(&self) -> String {
+        let mode = MODES
+            .iter()
+            .find(|(mode, _)| *mode == self.mode)
+            .map_or("one-player", |(_, name)| name);
+        let mut src = format!(
+            "mode = {mode}\ntrack = {}\nlaps = {}\ncountdown = {}\nitems = {}\n\
+             enemies = {}\nseed = {}\ndifficulty = {}\n",
+            self.track,
+            self.rules.laps,
+            self.rules.countdown,
+            self.rules.items.name(),
+            self.rules.enemies,
+            self.rules.seed,
+            self.difficulty.name(),
+        );
+
+        for racer in &self.racers {
+            src.push_str(&format!("racer = {racer}\n"));
+        }
+        for inputs in &self.inputs {
+            src.push_str(&format!("inputs = {}\n", encode_inputs(inputs)));
+        }
+
+        src
+    }
+
+    //Saves the replay under a name that isn't taken yet
+    //and returns the path it was saved to
+    pub fn save(&self) -> Result<String, String> {
+        let date = records::today();
+        let path = (1..)
+            .map(|i| format!("{REPLAY_DIR}/{}-{date}-{i}.txt", self.track))
+            .find(|path| !Path::new(path).exists())
+            .unwrap_or_default();
+
+        if let Some(dir) = Path::new(&path).parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+        }
+        fs::write(&path, self.to_src()).map_err(|e| format!("{path}: {e}"))?;
+        Ok(path)
+    }
+
+    fn parse(src: &str) -> Result<Replay, String> {
+        let mut mode = None;
+        let mut track = None;
+        let mut rules = RaceRules {
+            laps: 1,
+            countdown: race::DEFAULT_COUNTDOWN,
+            items: ItemOdds::Balanced,
+            enemies: true,
+            seed: 0,
+        };
+        let mut difficulty = Difficulty::Normal;
+        let mut racers = vec![];
+        let mut inputs = vec![];
+
+        for (i, line) in src.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+
+            let (key, value) = line
+                .split_once('=')
+                .ok_or(format!("line {}: expected `key = value`", i + 1))?;
+            let (key, value) = (key.trim(), value.trim());
+            let unknown = || format!("line {}: unknown {key} `{value}`", i + 1);
+            let number_error = |e: String| format!("line {}: {value}: {e}", i + 1);
+
+            match key {
+                "mode" => {
+                    let found = MODES.iter().find(|(_, name)| *name == value);
+                    mode = Some(found.ok_or_else(unknown)?.0);
+                }
+                "track" => track = Some(String::from(value)),
+                "laps" => rules.laps = value.parse().map_err(|e| number_error(format!("{e}")))?,
+                "countdown" => {
+                    rules.countdown = value.parse().map_err(|e| number_error(format!("{e}")))?
+                }
+                "items" => {
+                    rules.items = [ItemOdds::Off, ItemOdds::Balanced, ItemOdds::Random]
+                        .into_iter()
+                        .find(|odds| odds.name() == value)
+                        .ok_or_else(unknown)?
+                }
+                "enemies" => {
+                    rules.enemies = match value {
+                        "true" => true,
+                        "false" => false,
+                        _ => return Err(unknown()),
+                    }
+                }
+                "seed" => rules.seed = value.parse().map_err(|e| number_error(format!("{e}")))?,
+                "difficulty" => {
+                    difficulty = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard]
+                        .into_iter()
+                        .find(|difficulty| difficulty.name() == value)
+                        .ok_or_else(unknown)?
+                }
+                "racer" => racers.push(String::from(value)),
+                "inputs" => {
+                    inputs.push(decode_inputs(value).map_err(|e| format!("line {}: {e}", i + 1))?)
+                }
+                _ => return Err(format!("line {}: unknown replay property `{key}`", i + 1)),
+            }
+        }
+
+        let mode = mode.ok_or("missing mode")?;
+        let track = track.ok_or("missing track")?;
+        let players = Self::player_count(mode);
+        let racer_count = Self::racer_count(mode);
+        if racers.len() < racer_count {
+            return Err(format!("needs {racer_count} racers, got {}", racers.len()));
+        }
+        if inputs.len() != players {
+            return Err(format!("expected inputs for {players} players"));
+        }
+
+        Ok(Replay {
+            mode,
+            track,
+            rules,
+            difficulty,
+            racers,
+            inputs,
+        })
+    }
+
+    pub fn load(path: &str) -> Result<Replay, String> {
+        match fs::read_to_string(path) {
+            Ok(src) => Self::parse(&src).map_err(|e| format!("{path}: {e}")),
+            Err(msg) => {
+                eprintln!("{msg}");
+                eprintln!("Cannot open replay: {path}");
+                Err(String::from("Failed to open replay"))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::character;
+    use crate::oneplayer::SingeplayerState;
+    use crate::TICK;
+
+    fn bits(inputs: &[KartInput]) -> Vec<u8> {
+        inputs.iter().map(input_bits).collect()
+    }
+
+    #[test]
+    fn every_button_combination_is_kept() {
+        for bits in 0..64 {
+            assert_eq!(input_bits(&input_from_bits(bits)), bits);
+        }
+    }
+
+    #[test]
+    fn held_buttons_are_written_once() {
+        let inputs: Vec<KartInput> = [0, 0, 0, 1, 1, 9, 0, 63, 63, 63]
+            .into_iter()
+            .map(input_from_bits)
+            .collect();
+
+        let src = encode_inputs(&inputs);
+        assert_eq!(src, "3x0 2x1 1x9 1x0 3x63");
+        assert_eq!(bits(&decode_inputs(&src).unwrap()), bits(&inputs));
+        assert!(decode_inputs("3").is_err());
+        assert!(decode_inputs("3x64x1").is_err());
+    }
+
+    #[test]
+    fn runs_longer_than_a_replay_can_be_are_refused() {
+        assert_eq!(
+            decode_inputs(&format!("{MAX_TICKS}x1")).unwrap().len(),
+            MAX_TICKS
+        );
+        assert!(decode_inputs(&format!("{MAX_TICKS}x1 1x0")).is_err());
+        assert!(decode_inputs("99999999999x0").is_err());
+        assert!(decode_inputs(&format!("1x0 {}x0", usize::MAX)).is_err());
+    }
+
+    #[test]
+    fn a_two_player_replay_is_read_back() {
+        let saved = Replay {
+            mode: GameScreen::TwoPlayer,
+            track: String::from("crab-circuit"),
+            rules: RaceRules {
+                laps: 3,
+                countdown: 2.0,
+                items: ItemOdds::Random,
+                enemies: false,
+                seed: 1234,
+            },
+            difficulty: Difficulty::Hard,
+            racers: vec![String::from("Red Crab"), String::from("Blue Crab")],
+            inputs: vec![
+                [1, 1, 5, 0].into_iter().map(input_from_bits).collect(),
+                [17].into_iter().map(input_from_bits).collect(),
+            ],
+        };
+
+        let loaded = Replay::parse(&saved.to_src()).unwrap();
+        assert!(loaded.mode == GameScreen::TwoPlayer);
+        assert_eq!(loaded.rules.countdown, 2.0);
+        assert!(loaded.rules.items == ItemOdds::Random);
+        assert!(!loaded.rules.enemies);
+        assert_eq!(loaded.rules.seed, 1234);
+        assert!(loaded.difficulty == Difficulty::Hard);
+        assert_eq!(loaded.racers, saved.racers);
+        assert_eq!(bits(&loaded.inputs[1]), vec![17]);
+        //The second player let go of everything after their inputs ran out
+        assert_eq!(loaded.ticks(), 4);
+        assert_eq!(input_bits(&loaded.input(1, 3)), 0);
+
+        let one_inputs_line = saved.to_src().replacen("inputs = 1x17\n", "", 1);
+        assert!(Replay::parse(&one_inputs_line).is_err());
+    }
+
+    #[test]
+    fn a_one_player_replay_needs_the_whole_grid() {
+        let src = "mode = one-player\ntrack = crab-circuit\n\
+                   racer = Red Crab\nracer = Blue Crab\ninputs = 10x1\n";
+        let err = Replay::parse(src).err().unwrap();
+        assert!(
+            err.starts_with(&format!("needs {} racers", KART_SLOTS.len())),
+            "{err}"
+        );
+        //A time trial only has the player's kart
+        assert!(Replay::parse(&src.replace("one-player", "time-trial")).is_ok());
+    }
+
+    #[test]
+    fn enemies_are_either_true_or_false() {
+        let src = "mode = time-trial\ntrack = crab-circuit\nracer = Red Crab\ninputs = 10x1\n";
+        let enemies = |value: &str| Replay::parse(&format!("enemies = {value}\n{src}"));
+        assert!(enemies("true").unwrap().rules.enemies);
+        assert!(!enemies("false").unwrap().rules.enemies);
+        assert!(enemies("yes").is_err());
+    }
+
+    #[test]
+    fn replaying_the_inputs_gives_the_same_race() {
+        let level = Level::load_from_png("assets/tracks/crab-circuit.png").unwrap();
+        let characters = character::load_characters(character::CHARACTERS_PATH).unwrap();
+        let racers: Vec<&Character> = characters.iter().take(KART_SLOTS.len()).collect();
+        let rules = RaceRules {
+            laps: 1,
+            seed: 42,
+            ..RaceRules::new(&level)
+        };
+        let race_ticks = (90.0 / TICK) as usize;
+
+        //Drive forward weaving from side to side and use items as they come
+        let mut race = SingeplayerState::init(&level, rules, Difficulty::Hard, &racers);
+        let mut replay = Replay::new(
+            GameScreen::OnePlayer,
+            &level,
+            rules,
+            Difficulty::Hard,
+            &racers,
+        );
+        for tick in 0..race_ticks {
+            let steer = [0, 8, 0, 16][tick / 60 % 4];
+            let use_item = if tick % 240 == 0 { 32 } else { 0 };
+            let input = input_from_bits(1 | steer | use_item);
+            replay.record(&[input]);
+            race.update(&input, &level, TICK);
+        }
+
+        let replay = Replay::parse(&replay.to_src()).unwrap();
+        let racers = replay.racers(&characters).unwrap();
+        let mut replayed =
+            SingeplayerState::init(&level, replay.rules, replay.difficulty, &racers).set_replay();
+        for tick in 0..replay.ticks() {
+            replayed.update(&replay.input(0, tick), &level, TICK);
+        }
+
+        let (raced, replayed) = (race.results(), replayed.results());
+        assert!(raced.iter().any(|result| result.finish_time.is_some()));
+        for (raced, replayed) in raced.iter().zip(&replayed) {
+            assert_eq!(raced.position, replayed.position);
+            assert_eq!(raced.finish_time, replayed.finish_time);
+            assert_eq!(raced.lap_times, replayed.lap_times);
+            assert_eq!(raced.items_used, replayed.items_used);
+        }
+    }
+}
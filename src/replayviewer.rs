@@ -0,0 +1,251 @@
+use sdl2::{
+    keyboard::Keycode,
+    pixels::Color,
+    render::{Canvas, Texture, TextureCreator},
+    ttf::Font,
+    video::{Window, WindowContext},
+};
+use std::collections::HashMap;
+
+use crate::{
+    character::Character, count_ticks, display, events::Events, level::Level, menu::Text,
+    oneplayer::SingeplayerState, replay::Replay, sprite::SpriteType, twoplayer::TwoplayerState,
+    GameScreen, MAX_TICKS_PER_FRAME, TICK,
+};
+
+//How fast the replay can be played back
+const SPEEDS: [f64; 5] = [0.25, 0.5, 1.0, 2.0, 4.0];
+const NORMAL_SPEED: usize = 2;
+
+//The race being played back, it runs through the same code as when it was
+//raced so it plays out the same way when it is fed the recorded inputs
+enum ReplayRace {
+    OnePlayer(Box<SingeplayerState>),
+    TwoPlayer(Box<TwoplayerState>),
+}
+
+//Plays a recorded race back, it can be paused, sped up, slowed
+//down and the camera can follow any of the karts
+pub struct ReplayViewer {
+    replay: Replay,
+    race: ReplayRace,
+    tick: usize, //Next tick of the replay to run
+    tick_accumulator: f64,
+    paused: bool,
+    speed: usize,                //Index into SPEEDS
+    watching: usize,             //Index of the kart that the camera follows
+    pub exit_screen: GameScreen, //Where to go after leaving the replay
+}
+
+impl ReplayViewer {
+    //The racers need to be the karts named in the replay
+    pub fn init(
+        replay: Replay,
+        level: &Level,
+        racers: &[&Character],
+        exit_screen: GameScreen,
+    ) -> Self {
+        let race = match replay.mode {
+            GameScreen::TwoPlayer => {
+                ReplayRace::TwoPlayer(Box::new(TwoplayerState::init(level, replay.rules, racers)))
+            }
+            GameScreen::TimeTrial => ReplayRace::OnePlayer(Box::new(
+                SingeplayerState::init_time_trial(level, replay.rules, racers[0]).set_replay(),
+            )),
+            _ => ReplayRace::OnePlayer(Box::new(
+                SingeplayerState::init(level, replay.rules, replay.difficulty, racers).set_replay(),
+            )),
+        };
+
+        Self {
+            replay,
+            race,
+            tick: 0,
+            tick_accumulator: 0.0,
+            paused: false,
+            speed: NORMAL_SPEED,
+            watching: 0,
+            exit_screen,
+        }
+    }
+
+    fn finished(&self) -> bool {
+        self.tick >= self.replay.ticks()
+    }
+
+    fn kart_count(&self) -> usize {
+        match &self.race {
+            ReplayRace::OnePlayer(state) => state.kart_count(),
+            ReplayRace::TwoPlayer(state) => state.kart_count(),
+        }
+    }
+
+    fn watch_kart(&mut self, index: usize) {
+        self.watching = index % self.kart_count();
+        match &mut self.race {
+            ReplayRace::OnePlayer(state) => state.watch_kart(self.watching),
+            ReplayRace::TwoPlayer(state) => state.watch_kart(self.watching),
+        }
+    }
+
+    //Space pauses, up and down change the speed, left and right
+    //change which kart is followed, returns true when leaving the replay
+    pub fn press_buttons(&mut self, events: &mut Events) -> bool {
+        if events.key_is_pressed_once(Keycode::Space) {
+            self.paused = !self.paused;
+        }
+
+        if events.key_is_pressed_once(Keycode::Up) {
+            self.speed = (self.speed + 1).min(SPEEDS.len() - 1);
+        }
+
+        if events.key_is_pressed_once(Keycode::Down) {
+            self.speed = self.speed.saturating_sub(1);
+        }
+
+        if events.key_is_pressed_once(Keycode::Right) {
+            self.watch_kart(self.watching + 1);
+        }
+
+        if events.key_is_pressed_once(Keycode::Left) {
+            self.watch_kart(self.watching + self.kart_count() - 1);
+        }
+
+        events.key_is_pressed_once(Keycode::Escape)
+    }
+
+    //Runs as many ticks of the replay as fit into
+    //the last frame at the current speed
+    pub fn update(&mut self, level: &Level, frame_time: f64) {
+        if self.paused {
+            return;
+        }
+
+        //A sped up replay runs more ticks in a frame, so the limit grows with
+        //the speed or a fast replay would play slower at a low frame rate
+        let speed = SPEEDS[self.speed];
+        let max_ticks = (MAX_TICKS_PER_FRAME as f64 * speed).ceil() as u32;
+        let ticks = count_ticks(&mut self.tick_accumulator, frame_time * speed, max_ticks);
+        for _ in 0..ticks {
+            if self.finished() {
+                break;
+            }
+
+            let tick = self.tick;
+            match &mut self.race {
+                ReplayRace::OnePlayer(state) => {
+                    state.update(&self.replay.input(0, tick), level, TICK)
+                }
+                ReplayRace::TwoPlayer(state) => {
+                    let inputs = [self.replay.input(0, tick), self.replay.input(1, tick)];
+                    state.update(level, &inputs, TICK)
+                }
+            }
+            self.tick += 1;
+        }
+
+        let t = if self.finished() {
+            1.0
+        } else {
+            self.tick_accumulator / TICK
+        };
+        match &mut self.race {
+            ReplayRace::OnePlayer(state) => state.set_interpolation(t),
+            ReplayRace::TwoPlayer(state) => state.set_interpolation(t),
+        }
+    }
+
+    pub fn display_race(
+        &mut self,
+        canvas: &mut Canvas<Window>,
+        level: &Level,
+        pixel_buffer: &mut [u8],
+        background_texture: &mut Texture,
+        sprite_assets: &mut HashMap<SpriteType, Texture>,
+    ) -> Result<(), String> {
+        match &mut self.race {
+            ReplayRace::OnePlayer(state) => {
+                state.create_background_texture(pixel_buffer, level, background_texture)?;
+                state.display(canvas, background_texture)?;
+                state.display_sprites(canvas, sprite_assets)?;
+            }
+            ReplayRace::TwoPlayer(state) => {
+                state.display_views(
+                    canvas,
+                    level,
+                    pixel_buffer,
+                    background_texture,
+                    sprite_assets,
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    //Draws the race's own hud with the replay controls at the bottom of the screen
+    pub fn display_hud(
+        &self,
+        canvas: &mut Canvas<Window>,
+        texture_creator: &TextureCreator<WindowContext>,
+        font: &Font,
+        powerup_icons: &[Texture],
+    ) -> Result<(), String> {
+        match &self.race {
+            ReplayRace::OnePlayer(state) => {
+                state.display_hud(canvas, texture_creator, font, powerup_icons)?
+            }
+            ReplayRace::TwoPlayer(state) => {
+                state.display_hud(canvas, texture_creator, font, powerup_icons)?
+            }
+        }
+
+        let (canv_w, canv_h) = canvas.output_size()?;
+        let status = if self.finished() {
+            "END"
+        } else if self.paused {
+            "PAUSED"
+        } else {
+            ""
+        };
+        let watching = self
+            .replay
+            .racers
+            .get(self.watching)
+            .map_or("", |name| name.as_str());
+        let replay_text = Text::new(
+            format!(
+                "REPLAY x{} {} / {} {status}",
+                SPEEDS[self.speed],
+                display::format_time(self.tick as f64 * TICK),
+                display::format_time(self.replay.ticks() as f64 * TICK),
+            )
+            .as_str(),
+            16,
+            canv_h as i32 - 72,
+            Color::RGB(255, 128, 0),
+            16,
+        );
+        replay_text.display_left_justify(canvas, texture_creator, font)?;
+
+        let watching_text = Text::new(
+            format!("Watching: {watching}").as_str(),
+            canv_w as i32 - 16,
+            canv_h as i32 - 72,
+            Color::RGB(255, 128, 0),
+            16,
+        );
+        watching_text.display_right_justify(canvas, texture_creator, font)?;
+
+        let controls_text = Text::new(
+            "Space: pause  Up/Down: speed  Left/Right: change kart  Esc: leave",
+            canv_w as i32 / 2,
+            canv_h as i32 - 32,
+            Color::WHITE,
+            8,
+        );
+        controls_text.display_center(canvas, texture_creator, font)?;
+
+        Ok(())
+    }
+}
@@ -0,0 +1,366 @@
+use sdl2::{
+    keyboard::Keycode,
+    pixels::Color,
+    render::{Canvas, TextureCreator},
+    ttf::Font,
+    video::{Window, WindowContext},
+};
+
+use crate::{
+    display,
+    events::Events,
+    level::Level,
+    menu::{Button, Text, TextField},
+    race::{self, RaceRules, RacerResult},
+    records::{self, Record, RecordType, Records},
+    GameScreen,
+};
+
+const MAX_NAME_LEN: usize = 12;
+//Position of each column of the results table relative to the center of the
+//screen and whether it is left justified (the numbers are right justified)
+const COLUMNS: [(i32, bool); 6] = [
+    (-400, true),
+    (-336, true),
+    (16, false),
+    (176, false),
+    (296, false),
+    (400, false),
+];
+
+//What the player picked on the results screen
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ResultsChoice {
+    Retry,
+    NextTrack,
+    MainMenu,
+    WatchReplay,
+    SaveReplay,
+}
+
+//Shown after a race, lists how every kart did and lets
+//the player enter their name if they set a record
+pub struct ResultsScreen {
+    pub mode: GameScreen, //The kind of race that was finished
+    track: String,
+    track_name: String,
+    laps: u32,
+    seed: u64,
+    results: Vec<RacerResult>,      //Sorted by finishing order
+    player_position: Option<usize>, //Where the player finished in a single player race
+    race_record: bool,
+    lap_record: bool,
+    saved: bool,
+    replay_path: Option<String>, //Where the replay of the race was saved
+    name_field: TextField,
+    save_button: Button,
+    retry_button: Button,
+    next_button: Button,
+    menu_button: Button,
+    watch_button: Button,
+    save_replay_button: Button,
+}
+
+impl ResultsScreen {
+    //In a single player race the player's result is the first one
+    pub fn init(
+        mode: GameScreen,
+        level: &Level,
+        rules: RaceRules,
+        mut results: Vec<RacerResult>,
+        records: &Records,
+    ) -> Self {
+        let laps = rules.laps;
+        //Records are only set by a single player
+        let player = results
+            .first()
+            .filter(|_| mode == GameScreen::OnePlayer || mode == GameScreen::TimeTrial);
+        let race_record = player
+            .and_then(|player| player.finish_time)
+            .is_some_and(|time| records.qualifies(RecordType::Race, &level.id, laps, time));
+        let lap_record = player
+            .and_then(|player| player.best_lap())
+            .is_some_and(|time| records.qualifies(RecordType::Lap, &level.id, laps, time));
+        let player_position = player.map(|player| player.position);
+        results.sort_by_key(|result| result.position);
+
+        Self {
+            mode,
+            track: level.id.clone(),
+            track_name: level.name.clone(),
+            laps,
+            seed: rules.seed,
+            results,
+            player_position,
+            race_record,
+            lap_record,
+            saved: false,
+            replay_path: None,
+            name_field: TextField::new(0, 160, MAX_NAME_LEN),
+            save_button: Button::new(264, 160, "Save"),
+            retry_button: Button::new(-288, 232, "Retry"),
+            next_button: Button::new(0, 232, "Next Track"),
+            menu_button: Button::new(288, 232, "Main Menu"),
+            watch_button: Button::new(-200, 176, "Watch Replay"),
+            save_replay_button: Button::new(200, 176, "Save Replay"),
+        }
+    }
+
+    pub fn replay_saved(&mut self, path: String) {
+        self.replay_path = Some(path);
+    }
+
+    fn entering_name(&self) -> bool {
+        (self.race_record || self.lap_record) && !self.saved
+    }
+
+    fn save_records(&mut self, records: &mut Records) {
+        let Some(player) = self
+            .player_position
+            .and_then(|position| self.results.iter().find(|r| r.position == position))
+        else {
+            return;
+        };
+
+        let name = match self.name_field.text.trim() {
+            "" => String::from("Player"),
+            name => String::from(name),
+        };
+        let date = records::today();
+        let mut new_records = vec![];
+
+        if let Some(race_time) = player.finish_time.filter(|_| self.race_record) {
+            new_records.push((RecordType::Race, race_time));
+        }
+        if let Some(best_lap) = player.best_lap().filter(|_| self.lap_record) {
+            new_records.push((RecordType::Lap, best_lap));
+        }
+
+        for (record_type, time) in new_records {
+            records.add(Record {
+                record_type,
+                track: self.track.clone(),
+                laps: self.laps,
+                time,
+                date: date.clone(),
+                name: name.clone(),
+            });
+        }
+
+        if let Err(msg) = records.save(records::RECORDS_PATH) {
+            eprintln!("{msg}");
+            eprintln!("Cannot save records: {}", records::RECORDS_PATH);
+        }
+        self.saved = true;
+    }
+
+    pub fn press_buttons(
+        &mut self,
+        events: &mut Events,
+        canvas_dimensions: (u32, u32),
+        records: &mut Records,
+    ) -> Option<ResultsChoice> {
+        if self.entering_name() {
+            self.name_field.update(events);
+
+            if self.save_button.clicked(events, canvas_dimensions)
+                || events.key_is_pressed_once(Keycode::Return)
+            {
+                self.save_records(records);
+            }
+        } else if self.retry_button.clicked(events, canvas_dimensions) {
+            return Some(ResultsChoice::Retry);
+        } else if self.next_button.clicked(events, canvas_dimensions) {
+            return Some(ResultsChoice::NextTrack);
+        } else if self.menu_button.clicked(events, canvas_dimensions)
+            || events.key_is_pressed_once(Keycode::Escape)
+        {
+            return Some(ResultsChoice::MainMenu);
+        } else if self.watch_button.clicked(events, canvas_dimensions) {
+            return Some(ResultsChoice::WatchReplay);
+        } else if self.replay_path.is_none()
+            && self.save_replay_button.clicked(events, canvas_dimensions)
+        {
+            return Some(ResultsChoice::SaveReplay);
+        }
+
+        None
+    }
+
+    fn display_row(
+        canvas: &mut Canvas<Window>,
+        texture_creator: &TextureCreator<WindowContext>,
+        font: &Font,
+        columns: [&str; 6],
+        y: i32,
+        color: Color,
+    ) -> Result<(), String> {
+        let (canv_w, _) = canvas.output_size()?;
+        let center_x = canv_w as i32 / 2;
+
+        for (text, &(x, left_justify)) in columns.iter().zip(COLUMNS.iter()) {
+            let column_text = Text::new(text, center_x + x, y, color, 16);
+            if left_justify {
+                column_text.display_left_justify(canvas, texture_creator, font)?;
+            } else {
+                column_text.display_right_justify(canvas, texture_creator, font)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn display_results(
+        &self,
+        canvas: &mut Canvas<Window>,
+        texture_creator: &TextureCreator<WindowContext>,
+        font: &Font,
+    ) -> Result<(), String> {
+        let (canv_w, canv_h) = canvas.output_size()?;
+        let center_x = canv_w as i32 / 2;
+        let top = canv_h as i32 / 2 - 150;
+
+        Self::display_row(
+            canvas,
+            texture_creator,
+            font,
+            ["Pos", "Name", "Time", "Best lap", "Items", "KOs"],
+            top,
+            Color::RGB(255, 128, 0),
+        )?;
+
+        for (i, result) in self.results.iter().enumerate() {
+            let y = top + 32 + i as i32 * 52;
+            //Highlight the player
+            let color = if Some(result.position) == self.player_position {
+                Color::RGB(255, 128, 0)
+            } else {
+                Color::WHITE
+            };
+
+            let finish_time = result
+                .finish_time
+                .map(display::format_time)
+                .unwrap_or(String::from("-"));
+            let best_lap = result
+                .best_lap()
+                .map(display::format_time)
+                .unwrap_or(String::from("-"));
+            Self::display_row(
+                canvas,
+                texture_creator,
+                font,
+                [
+                    race::ordinal(result.position).as_str(),
+                    result.name.as_str(),
+                    finish_time.as_str(),
+                    best_lap.as_str(),
+                    result.items_used.to_string().as_str(),
+                    result.knockouts.to_string().as_str(),
+                ],
+                y,
+                color,
+            )?;
+
+            //Time of every lap under the rest of the row
+            let lap_times: Vec<String> = result
+                .lap_times
+                .iter()
+                .map(|&time| display::format_time(time))
+                .collect();
+            let laps_text = Text::new(
+                format!("Laps: {}", lap_times.join(" ")).as_str(),
+                center_x - 336,
+                y + 32,
+                color,
+                8,
+            );
+            laps_text.display_left_justify(canvas, texture_creator, font)?;
+        }
+
+        Ok(())
+    }
+
+    pub fn display(
+        &self,
+        canvas: &mut Canvas<Window>,
+        texture_creator: &TextureCreator<WindowContext>,
+        events: &Events,
+        font: &Font,
+    ) -> Result<(), String> {
+        let (canv_w, canv_h) = canvas.output_size()?;
+        let center_x = canv_w as i32 / 2;
+
+        let title_text = Text::new("Results", center_x, 16, Color::WHITE, 32);
+        title_text.display_center(canvas, texture_creator, font)?;
+
+        let track_text = Text::new(
+            format!("{} - {} laps", self.track_name, self.laps).as_str(),
+            center_x,
+            80,
+            Color::WHITE,
+            16,
+        );
+        track_text.display_center(canvas, texture_creator, font)?;
+
+        //The seed lets the same race be played again with --seed
+        let seed_text = Text::new(
+            format!("Seed: {}", self.seed).as_str(),
+            16,
+            16,
+            Color::WHITE,
+            8,
+        );
+        seed_text.display_left_justify(canvas, texture_creator, font)?;
+
+        if let Some(path) = &self.replay_path {
+            let replay_text = Text::new(
+                format!("Replay saved: {path}").as_str(),
+                16,
+                40,
+                Color::WHITE,
+                8,
+            );
+            replay_text.display_left_justify(canvas, texture_creator, font)?;
+        }
+
+        self.display_results(canvas, texture_creator, font)?;
+
+        if self.entering_name() {
+            let record_text = if self.race_record && self.lap_record {
+                "New race and lap record!"
+            } else if self.race_record {
+                "New race record!"
+            } else {
+                "New lap record!"
+            };
+            let new_record_text = Text::new(
+                format!("{record_text} Enter your name:").as_str(),
+                center_x,
+                canv_h as i32 / 2 + 96,
+                Color::RGB(255, 128, 0),
+                16,
+            );
+            new_record_text.display_center(canvas, texture_creator, font)?;
+
+            self.name_field.display(canvas, texture_creator, font)?;
+            self.save_button
+                .display(canvas, texture_creator, events, font)?;
+        } else {
+            self.retry_button
+                .display(canvas, texture_creator, events, font)?;
+            self.next_button
+                .display(canvas, texture_creator, events, font)?;
+            self.menu_button
+                .display(canvas, texture_creator, events, font)?;
+            self.watch_button
+                .display(canvas, texture_creator, events, font)?;
+            if self.replay_path.is_none() {
+                self.save_replay_button
+                    .display(canvas, texture_creator, events, font)?;
+            }
+        }
+
+        Ok(())
+    }
+}
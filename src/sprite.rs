@@ -5,11 +5,15 @@ use sdl2::video::Window;
 use std::collections::HashMap;
 use std::f64::consts::PI;
 
+pub mod ai_kart;
 pub mod bitmap;
 pub mod enemy;
+pub mod ghost;
 pub mod kart;
 pub mod load_assets;
+pub mod particle;
 pub mod powerup;
+pub mod projectile;
 
 const DEFAULT_SPRITE_SIZE: f64 = 24.0 / 256.0;
 pub const SPRITE_SIZE: f64 = 0.1;
@@ -21,6 +25,12 @@ pub fn dist_between(spr1: &Sprite, spr2: &Sprite) -> f64 {
         .sqrt()
 }
 
+//Blends between two angles (in radians) taking the shortest way around
+pub fn lerp_angle(from: f64, to: f64, t: f64) -> f64 {
+    let turn = (to - from + PI).rem_euclid(PI * 2.0) - PI;
+    from + turn * t
+}
+
 pub fn xz_diff_norm(spr1: &Sprite, spr2: &Sprite) -> (f64, f64) {
     let dist = dist_between(spr1, spr2);
     let xdiff = spr1.trans_x - spr2.trans_x;
@@ -83,24 +93,33 @@ fn get_rect_y_offset(canvas_dimensions: (u32, u32), buff_w: usize, buff_h: usize
 pub enum SpriteType {
     Kart1,
     Kart2,
+    Kart3,
+    Kart4,
     Checkpoint1,
     Checkpoint2,
-    Banana,
     Enemy,
-    Fireball,
     Powerup,
+    Dust,
+    Sparkle,
+    SparkBlue,
+    SparkOrange,
+    Item(usize), //Items on the track, uses the index of the item in ITEMS
 }
 
+#[derive(Clone, Copy)]
 pub struct Sprite {
     pub trans_x: f64,
     pub trans_z: f64,
     pub rotation: f64,
     pub rotation_speed: f64,
     pub camera_kart: bool, //Stores if it is the kart that is focused on in the camera
+    pub drifting: bool,    //Drifting karts are drawn turned further
     pub width: f64,
     pub height: f64,
     pub frame_count: i32, //Number of rotation frames that the sprite has
     pub sprite_type: SpriteType,
+    pub alpha: u8,
+    prev: (f64, f64, f64), //Position and rotation at the start of the last tick
 }
 
 impl Sprite {
@@ -114,7 +133,10 @@ impl Sprite {
             height: DEFAULT_SPRITE_SIZE,
             frame_count: 1,
             camera_kart: false,
+            drifting: false,
             sprite_type: spr_type,
+            alpha: 255,
+            prev: (x, z, 0.0),
         }
     }
 
@@ -129,7 +151,27 @@ impl Sprite {
             height: DEFAULT_SPRITE_SIZE,
             frame_count: 1,
             camera_kart: false,
+            drifting: false,
             sprite_type: spr_type,
+            alpha: 255,
+            prev: (x, z, 0.0),
+        }
+    }
+
+    //Remembers where the sprite is at the start of a tick
+    pub fn save_position(&mut self) {
+        self.prev = (self.trans_x, self.trans_z, self.rotation);
+    }
+
+    //Copy of the sprite part of the way (0 to 1) between where it was
+    //at the start of the last tick and where it is now, used for drawing
+    pub fn interpolated(&self, t: f64) -> Sprite {
+        let (x, z, rotation) = self.prev;
+        Sprite {
+            trans_x: x + (self.trans_x - x) * t,
+            trans_z: z + (self.trans_z - z) * t,
+            rotation: lerp_angle(rotation, self.rotation, t),
+            ..*self
         }
     }
 
@@ -157,7 +199,7 @@ impl Sprite {
         buff_dimensions: (usize, usize),
         canvas_dimensions: (u32, u32),
         canvas_origin: (i32, i32),
-        sprite_assets: &HashMap<SpriteType, Texture>,
+        sprite_assets: &mut HashMap<SpriteType, Texture>,
     ) -> Result<(), String> {
         let (buff_w, buff_h) = buff_dimensions;
         let (cam_x, cam_z) = camera_translate(self.trans_x, self.trans_z, cam);
@@ -193,7 +235,8 @@ impl Sprite {
         };
 
         if sprite_h > 0.005 {
-            if let Some(tex) = sprite_assets.get(&self.sprite_type) {
+            if let Some(tex) = sprite_assets.get_mut(&self.sprite_type) {
+                tex.set_alpha_mod(self.alpha);
                 let frame = self.get_rotation_frame(cam);
                 let tex_rect = Rect::new(frame * 32, 0, 32, 32);
                 canv.copy(tex, tex_rect, sprite_rect)
@@ -212,7 +255,17 @@ impl Sprite {
     //this is to create a sense of rotation when driving the kart and give the
     //player feedback on where they are turning as if we just used set_rotation_frame,
     //as the camera follows the sprite it will always appear to be the same rotation
+    //the frame follows the way the kart is rotating so it also matches the camera
+    //when reversing (steering left while reversing rotates the kart to the right)
     fn get_kart_rotation_frame(&self) -> i32 {
+        //A drifting kart reuses the rotation frame one step past the turning
+        //frame so it looks turned further into the slide
+        if self.drifting && self.rotation_speed < 0.0 {
+            return self.frame_count - 2;
+        } else if self.drifting {
+            return 2;
+        }
+
         if self.rotation_speed < -0.05 {
             //Turning to the left
             return self.frame_count - 1;
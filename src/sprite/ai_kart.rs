@@ -0,0 +1,215 @@
+use crate::level::Level;
+use crate::sprite::kart::{Kart, KartInput};
+use std::f64::consts::PI;
+
+//How close a computer kart needs to get to a waypoint before it
+//starts driving towards the next one
+const WAYPOINT_RADIUS: f64 = 0.6;
+//How sharp the turn after the next waypoint needs to be (in radians)
+//before the kart slows down for it
+const SHARP_TURN: f64 = 0.6;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Difficulty {
+    Easy,
+    Normal,
+    Hard,
+}
+
+//How a computer kart drives at each difficulty
+struct DrivingStyle {
+    top_speed: f64,
+    corner_speed: f64, //Fastest the kart will take a sharp turn
+    slow_angle: f64,   //Stop accelerating if the kart is facing this far away from the waypoint
+    brake_dist: f64,   //How far away from a sharp turn the kart starts slowing down
+    deadzone: f64,     //Don't steer if the kart is facing this close to the waypoint
+    item_delay: f64,   //How long the kart holds on to an item before using it
+}
+
+impl Difficulty {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+        }
+    }
+
+    pub fn next(&self) -> Self {
+        match self {
+            Difficulty::Easy => Difficulty::Normal,
+            Difficulty::Normal => Difficulty::Hard,
+            Difficulty::Hard => Difficulty::Easy,
+        }
+    }
+
+    pub fn prev(&self) -> Self {
+        match self {
+            Difficulty::Easy => Difficulty::Hard,
+            Difficulty::Normal => Difficulty::Easy,
+            Difficulty::Hard => Difficulty::Normal,
+        }
+    }
+
+    fn driving_style(&self) -> DrivingStyle {
+        match self {
+            Difficulty::Easy => DrivingStyle {
+                top_speed: 2.2,
+                corner_speed: 1.0,
+                slow_angle: 0.4,
+                brake_dist: 4.0,
+                deadzone: 0.15,
+                item_delay: 3.0,
+            },
+            Difficulty::Normal => DrivingStyle {
+                top_speed: 2.6,
+                corner_speed: 1.2,
+                slow_angle: 0.35,
+                brake_dist: 4.5,
+                deadzone: 0.1,
+                item_delay: 2.0,
+            },
+            Difficulty::Hard => DrivingStyle {
+                top_speed: 2.8,
+                corner_speed: 1.3,
+                slow_angle: 0.35,
+                brake_dist: 5.5,
+                deadzone: 0.05,
+                item_delay: 1.0,
+            },
+        }
+    }
+}
+
+//Returns the angle (between -PI and PI) that a kart facing `rotation`
+//at (x, z) needs to turn to face (target_x, target_z)
+fn angle_to(rotation: f64, x: f64, z: f64, target: (f64, f64)) -> f64 {
+    let (target_x, target_z) = target;
+    let desired = (target_x - x).atan2(target_z - z);
+    (desired - rotation + PI).rem_euclid(PI * 2.0) - PI
+}
+
+//A kart that is driven by the computer, it follows the waypoints of
+//the track (or the checkpoints if the track doesn't have any)
+pub struct AiKart {
+    pub kart: Kart,
+    difficulty: Difficulty,
+    waypoint: usize,
+    item_timer: f64, //How long the kart has been holding its item
+}
+
+impl AiKart {
+    pub fn new(kart: Kart, difficulty: Difficulty, level: &Level) -> Self {
+        //Start by driving towards the closest waypoint
+        let (x, z) = (kart.sprite.trans_x, kart.sprite.trans_z);
+        let waypoint = (0..Self::waypoint_count(level))
+            .min_by(|&a, &b| {
+                let (ax, az) = Self::waypoint_position(level, a);
+                let (bx, bz) = Self::waypoint_position(level, b);
+                let dist_a = (ax - x) * (ax - x) + (az - z) * (az - z);
+                let dist_b = (bx - x) * (bx - x) + (bz - z) * (bz - z);
+                dist_a.partial_cmp(&dist_b).unwrap()
+            })
+            .unwrap_or(0);
+
+        Self {
+            kart,
+            difficulty,
+            waypoint,
+            item_timer: 0.0,
+        }
+    }
+
+    fn waypoint_count(level: &Level) -> usize {
+        if level.waypoints.is_empty() {
+            level.checkpoints.len()
+        } else {
+            level.waypoints.len()
+        }
+    }
+
+    fn waypoint_position(level: &Level, index: usize) -> (f64, f64) {
+        if level.waypoints.is_empty() {
+            level.checkpoint_position(index)
+        } else {
+            level.waypoints[index]
+        }
+    }
+
+    //Moves on to the next waypoint once the kart is close to
+    //the current one or has driven past it
+    fn update_waypoint(&mut self, level: &Level) {
+        let count = Self::waypoint_count(level);
+        let (x, z) = (self.kart.sprite.trans_x, self.kart.sprite.trans_z);
+
+        for _ in 0..count {
+            let (wx, wz) = Self::waypoint_position(level, self.waypoint);
+            let (px, pz) = Self::waypoint_position(level, (self.waypoint + count - 1) % count);
+            let dist = ((wx - x) * (wx - x) + (wz - z) * (wz - z)).sqrt();
+            let passed = (x - wx) * (wx - px) + (z - wz) * (wz - pz) > 0.0;
+
+            if dist >= WAYPOINT_RADIUS && !passed {
+                break;
+            }
+
+            self.waypoint = (self.waypoint + 1) % count;
+        }
+    }
+
+    //Returns true when the kart has held on to its item long enough to use it
+    pub fn use_item(&mut self, dt: f64) -> bool {
+        if self.kart.powerup_amt == 0 {
+            self.item_timer = 0.0;
+            return false;
+        }
+
+        self.item_timer += dt;
+        if self.item_timer < self.difficulty.driving_style().item_delay {
+            return false;
+        }
+
+        self.item_timer = 0.0;
+        true
+    }
+
+    //Decides how to drive the kart this frame
+    pub fn get_input(&mut self, level: &Level) -> KartInput {
+        if Self::waypoint_count(level) == 0 {
+            return KartInput::default();
+        }
+
+        self.update_waypoint(level);
+
+        let style = self.difficulty.driving_style();
+        let count = Self::waypoint_count(level);
+        let (x, z) = (self.kart.sprite.trans_x, self.kart.sprite.trans_z);
+        let rotation = self.kart.sprite.rotation;
+        let target = Self::waypoint_position(level, self.waypoint);
+        let after = Self::waypoint_position(level, (self.waypoint + 1) % count);
+
+        let diff = angle_to(rotation, x, z, target);
+        let dist = ((target.0 - x) * (target.0 - x) + (target.1 - z) * (target.1 - z)).sqrt();
+        //How far the kart will have to turn after reaching the waypoint
+        let turn = angle_to(rotation, x, z, after).abs();
+
+        //Slow down if the kart is facing away from the waypoint
+        //or is coming up to a sharp turn
+        let cornering =
+            diff.abs() > style.slow_angle || (turn > SHARP_TURN && dist < style.brake_dist);
+        let corner_speed = if turn > SHARP_TURN {
+            style.corner_speed * SHARP_TURN / turn
+        } else {
+            style.corner_speed
+        };
+
+        KartInput {
+            accelerate: self.kart.speed < style.top_speed
+                && (!cornering || self.kart.speed < corner_speed),
+            brake: false,
+            drift: false,
+            left: diff < -style.deadzone,
+            right: diff > style.deadzone,
+            use_item: false, //Computer karts use items on a timer instead
+        }
+    }
+}
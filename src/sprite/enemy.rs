@@ -4,12 +4,7 @@ pub struct Enemy {
     pub sprite: Sprite,
     start: (f64, f64),
     end: (f64, f64),
-}
-
-pub struct Fireball {
-    pub sprite: Sprite,
-    pub target: SpriteType,
-    pub timer: f64,
+    stun_timer: f64, //Stunned enemies stop moving and can't knock out karts
 }
 
 impl Enemy {
@@ -18,10 +13,24 @@ impl Enemy {
             sprite: Sprite::new(x, z, SpriteType::Enemy),
             start: (x, z),
             end: (endx, endz),
+            stun_timer: 0.0,
         }
     }
 
+    pub fn stun(&mut self, time: f64) {
+        self.stun_timer = time;
+    }
+
+    pub fn stunned(&self) -> bool {
+        self.stun_timer > 0.0
+    }
+
     pub fn update(&mut self, dt: f64) {
+        if self.stunned() {
+            self.stun_timer -= dt;
+            return;
+        }
+
         let diffx = self.end.0 - self.sprite.trans_x;
         let diffz = self.end.1 - self.sprite.trans_z;
         let dist = (diffx * diffx + diffz * diffz).sqrt();
@@ -37,30 +46,3 @@ impl Enemy {
         self.sprite.trans_z += speedz * dt;
     }
 }
-
-impl Fireball {
-    pub fn new(x: f64, z: f64, target_type: SpriteType) -> Fireball {
-        Fireball {
-            sprite: Sprite::new(x, z, SpriteType::Fireball),
-            timer: 0.0,
-            target: target_type,
-        }
-    }
-
-    pub fn update(&mut self, dt: f64, target: &Sprite) {
-        self.timer += dt;
-
-        let dist = ((target.trans_x - self.sprite.trans_x)
-            * (target.trans_x - self.sprite.trans_x)
-            + (target.trans_z - self.sprite.trans_z) * (target.trans_z - self.sprite.trans_z))
-            .sqrt();
-        let xdiff = target.trans_x - self.sprite.trans_x;
-        let zdiff = target.trans_z - self.sprite.trans_z;
-
-        let speedx = xdiff / dist * 4.0;
-        let speedz = zdiff / dist * 4.0;
-
-        self.sprite.trans_x += speedx * dt;
-        self.sprite.trans_z += speedz * dt;
-    }
-}
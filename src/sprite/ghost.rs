@@ -0,0 +1,154 @@
+use crate::level::Level;
+use crate::sprite::{lerp_angle, Sprite, SpriteType, SPRITE_SIZE};
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+
+const GHOST_DIR: &str = "saves/ghosts";
+//How see-through the ghost is (0 = invisible, 255 = solid)
+const GHOST_ALPHA: u8 = 112;
+
+#[derive(Clone, Copy)]
+struct GhostFrame {
+    time: f64,
+    x: f64,
+    z: f64,
+    rotation: f64,
+}
+
+//Where the best run on a track is saved, there is a
+//separate ghost for each number of laps
+pub fn ghost_path(level: &Level, laps: u32) -> String {
+    format!("{GHOST_DIR}/{}-{laps}.txt", level.id)
+}
+
+//Replays a recorded run as a see-through kart
+pub struct Ghost {
+    pub sprite: Sprite,
+    pub time: f64, //How long the run took to finish
+    frames: Vec<GhostFrame>,
+}
+
+//Records the position of a kart every frame so that it can be saved as a ghost
+pub struct GhostRecorder {
+    frames: Vec<GhostFrame>,
+}
+
+/*
+ * Ghost files are text, the first line is the time that it took to finish
+ * and every line after that is a frame of the run: <time> <x> <z> <rotation>
+ * */
+
+impl Ghost {
+    pub fn load(path: &str) -> Result<Ghost, String> {
+        let src = match fs::read_to_string(path) {
+            Ok(src) => src,
+            //There is no ghost until a run is finished on the track
+            Err(e) if e.kind() == ErrorKind::NotFound => return Err(format!("{path}: {e}")),
+            Err(e) => {
+                eprintln!("{e}");
+                eprintln!("Cannot open ghost: {path}");
+                return Err(String::from("Failed to open ghost"));
+            }
+        };
+
+        let mut lines = src.lines();
+        let time = lines
+            .next()
+            .ok_or(format!("{path}: empty ghost"))?
+            .trim()
+            .parse::<f64>()
+            .map_err(|e| format!("{path}: {e}"))?;
+
+        let mut frames = vec![];
+        for (i, line) in lines.enumerate() {
+            let numbers = line
+                .split_whitespace()
+                .map(|v| v.parse::<f64>())
+                .collect::<Result<Vec<f64>, _>>()
+                .map_err(|e| format!("{path}: line {}: {e}", i + 2))?;
+
+            if numbers.len() != 4 {
+                return Err(format!("{path}: line {}: expected 4 numbers", i + 2));
+            }
+
+            frames.push(GhostFrame {
+                time: numbers[0],
+                x: numbers[1],
+                z: numbers[2],
+                rotation: numbers[3],
+            });
+        }
+
+        let first = frames
+            .first()
+            .ok_or(format!("{path}: ghost has no frames"))?;
+        let mut sprite = Sprite::new(first.x, first.z, SpriteType::Kart1)
+            .set_size(SPRITE_SIZE, SPRITE_SIZE)
+            .set_framecount(8)
+            .set_rotation(first.rotation);
+        sprite.alpha = GHOST_ALPHA;
+
+        Ok(Ghost {
+            sprite,
+            time,
+            frames,
+        })
+    }
+
+    //Moves the ghost to where it was at the time in the race,
+    //stays at the finish line once the run is over
+    pub fn update(&mut self, time: f64) {
+        let next = self.frames.partition_point(|frame| frame.time < time);
+        let frame = if next == 0 || next >= self.frames.len() {
+            self.frames[next.min(self.frames.len() - 1)]
+        } else {
+            //Blend between the frames before and after the time
+            let prev = self.frames[next - 1];
+            let next = self.frames[next];
+            let t = (time - prev.time) / (next.time - prev.time);
+
+            GhostFrame {
+                time,
+                x: prev.x + (next.x - prev.x) * t,
+                z: prev.z + (next.z - prev.z) * t,
+                rotation: lerp_angle(prev.rotation, next.rotation, t),
+            }
+        };
+
+        self.sprite.trans_x = frame.x;
+        self.sprite.trans_z = frame.z;
+        self.sprite.rotation = frame.rotation;
+    }
+}
+
+impl GhostRecorder {
+    pub fn new() -> Self {
+        Self { frames: vec![] }
+    }
+
+    pub fn record(&mut self, time: f64, sprite: &Sprite) {
+        self.frames.push(GhostFrame {
+            time,
+            x: sprite.trans_x,
+            z: sprite.trans_z,
+            rotation: sprite.rotation,
+        });
+    }
+
+    //Writes the recorded run to a ghost file
+    pub fn save(&self, path: &str, time: f64) -> Result<(), String> {
+        let mut src = format!("{time}\n");
+        for frame in &self.frames {
+            src.push_str(&format!(
+                "{} {} {} {}\n",
+                frame.time, frame.x, frame.z, frame.rotation
+            ));
+        }
+
+        if let Some(dir) = Path::new(path).parent() {
+            fs::create_dir_all(dir).map_err(|e| format!("{}: {e}", dir.display()))?;
+        }
+        fs::write(path, src).map_err(|e| format!("{path}: {e}"))
+    }
+}
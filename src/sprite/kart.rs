@@ -1,33 +1,141 @@
 use crate::events::Events;
+use crate::item::registry::ItemId;
 use crate::level::Level;
-use crate::sprite::{Sprite, SpriteType, SPRITE_SIZE};
+use crate::race::LapTimer;
+use crate::sprite::{
+    dist_between, particle::Particle, xz_diff_norm, Sprite, SpriteType, SPRITE_SIZE,
+};
 use sdl2::keyboard::Keycode;
 
 const MAX_SPEED: f64 = 4.0;
 const MAX_ROTATION_SPEED: f64 = 0.4;
+const ACCELERATION: f64 = 0.7;
+//How long (in seconds) a kart needs to drive away from its next checkpoint
+//before it is considered to be going the wrong way
+const WRONG_WAY_TIME: f64 = 1.5;
+//Time between particles being kicked up by the kart
+const PARTICLE_INTERVAL: f64 = 0.05;
+//How much speed a kart keeps after hitting a wall
+const WALL_SPEED_PENALTY: f64 = 0.5;
+//How far in front of the kart's center walls are checked
+const WALL_CHECK_DIST: f64 = 0.05;
+//How far a kart is pushed back after driving straight into a wall
+const WALL_BOUNCE_DIST: f64 = 0.05;
+//How long it takes for a kart that left the track to be put back on the road
+const RESPAWN_TIME: f64 = 1.5;
+//How long a kart can't be hit after its shield blocks a hit, so that an
+//enemy that is still touching the kart on the next tick doesn't knock it out
+const SHIELD_BREAK_TIME: f64 = 1.0;
+//How quickly a kart slows down when braking
+const BRAKE_DECELERATION: f64 = 3.0;
+//How quickly a stopped kart speeds up backwards when the brake is held
+const REVERSE_ACCELERATION: f64 = 0.7;
+//Fastest a kart can reverse compared to the fastest it can drive forward
+const REVERSE_SPEED_SCALE: f64 = 0.3;
+//Slowest a kart can be going to start or keep drifting
+const DRIFT_MIN_SPEED: f64 = 1.0;
+//How much faster than normal a drifting kart turns when steering into the drift,
+//not steering and steering out of the drift
+const DRIFT_TURN_IN: f64 = 1.6;
+const DRIFT_TURN: f64 = 1.3;
+const DRIFT_TURN_OUT: f64 = 0.7;
+//How far (in radians) a drifting kart slides towards the outside of the turn
+const DRIFT_SLIDE_ANGLE: f64 = 0.25;
+//How long a drift has to last to reach each mini-turbo tier
+//and how much speed the kart gets when the drift ends
+const MINI_TURBO_TIERS: [(f64, f64); 2] = [(1.0, 0.6), (2.2, 1.2)];
+//How close to the center of a kart another kart has to be to bump into it
+const KART_RADIUS: f64 = 0.06;
+//How bouncy bumps between karts are (0 = no bounce, 1 = no speed is lost)
+const BUMP_RESTITUTION: f64 = 0.5;
+//How much of the sideways speed from a bump is left after a second
+const BUMP_DAMPING: f64 = 0.05;
 
-#[derive(Clone, Copy, PartialEq, Eq, Hash)]
-pub enum PowerupType {
-    Empty,
-    SpeedBoost,
-    Fireball,
-    Banana,
+//How a kart drives, different karts can be picked before a race
+#[derive(Clone, Copy)]
+pub struct KartStats {
+    pub acceleration: f64, //How quickly the kart speeds up
+    pub top_speed: f64,    //Multiplies the fastest that the kart can go on every surface
+    pub handling: f64,     //Fastest that the kart can turn
+    pub weight: f64,       //Heavier karts push lighter ones further when they bump
 }
 
-const POWERUP_TYPES: [PowerupType; 3] = [
-    PowerupType::SpeedBoost,
-    PowerupType::Fireball,
-    PowerupType::Banana,
-];
+impl Default for KartStats {
+    fn default() -> Self {
+        Self {
+            acceleration: ACCELERATION,
+            top_speed: 1.0,
+            handling: MAX_ROTATION_SPEED,
+            weight: 1.0,
+        }
+    }
+}
+
+//The controls for driving a kart for a single frame,
+//either read from the keyboard or decided by the computer
+#[derive(Clone, Copy, Default)]
+pub struct KartInput {
+    pub accelerate: bool,
+    pub brake: bool, //Slows down the kart and then reverses once it has stopped
+    pub drift: bool,
+    pub left: bool,
+    pub right: bool,
+    pub use_item: bool, //Only true for the tick that the item button is pressed
+}
+
+impl KartInput {
+    //acceleration key: acclerate forward
+    //brake key: brake and reverse
+    //drift key: hold while turning to drift
+    //left key: turn left
+    //right key: turn right
+    //item key: use the item the kart is holding
+    pub fn from_keys(
+        events: &mut Events,
+        acceleration_key: Keycode,
+        brake_key: Keycode,
+        drift_key: Keycode,
+        left_key: Keycode,
+        right_key: Keycode,
+        item_key: Keycode,
+    ) -> Self {
+        Self {
+            accelerate: events.key_is_pressed(acceleration_key),
+            brake: events.key_is_pressed(brake_key),
+            drift: events.key_is_pressed(drift_key),
+            left: events.key_is_pressed(left_key),
+            right: events.key_is_pressed(right_key),
+            use_item: events.key_is_pressed_once(item_key),
+        }
+    }
+}
 
 pub struct Kart {
     pub sprite: Sprite,
     pub knock_out: f64, //if the kart is knocked out, this is set to a nonzero value
-    pub powerup: PowerupType,
+    pub respawn_timer: f64, //if the kart is being put back on the road, this is nonzero
+    last_valid_pos: (f64, f64), //Last position where the kart was on the road
+    pub powerup: Option<ItemId>,
     pub powerup_amt: u16,
+    pub shielded: bool,      //Blocks the next hit
+    shield_break_timer: f64, //Time left that the kart can't be hit after its shield broke
     pub laps: u32,
     pub current_checkpoint: usize,
+    pub position: usize, //Place in the race (1 = first), 0 if the kart hasn't been ranked
+    pub lap_timer: LapTimer,
+    pub items_used: u32,
+    pub knockouts: u32, //How many times the kart has been knocked out in the race
     pub speed: f64,
+    bump_velocity: (f64, f64), //Sideways speed from being bumped by another kart
+    pub stats: KartStats,
+    prev_pos: (f64, f64), //Position the last time checkpoints were checked
+    pub wrong_way_timer: f64,
+    prev_checkpoint_dist: f64,
+    drift_direction: f64, //-1 if drifting to the left, 1 if drifting to the right, 0 if not drifting
+    drift_charge: f64,    //How long the kart has been drifting for
+    particle: Option<SpriteType>, //Particle that the surface under the kart kicks up
+    particle_timer: f64,
+    particle_left: bool,
     rotation_before_knockout: f64,
     acceleration: f64,
     friction: f64,
@@ -40,6 +148,42 @@ impl Kart {
         self.knock_out > 0.0
     }
 
+    //Knocks the kart out unless it has a shield, which blocks the hit
+    //and anything else that hits the kart soon after
+    pub fn hit(&mut self, time: f64) {
+        if self.shielded {
+            self.shielded = false;
+            self.shield_break_timer = SHIELD_BREAK_TIME;
+        } else if self.shield_break_timer <= 0.0 {
+            self.knock_out_for(time);
+        }
+    }
+
+    //Knocks the kart out for some time (in seconds)
+    //unless it is already knocked out
+    pub fn knock_out_for(&mut self, time: f64) {
+        if !self.knocked_out() {
+            self.knock_out = time;
+            self.knockouts += 1;
+        }
+    }
+
+    pub fn respawning(&self) -> bool {
+        self.respawn_timer > 0.0
+    }
+
+    pub fn drifting(&self) -> bool {
+        self.drift_direction != 0.0
+    }
+
+    //Mini-turbo tier that the current drift has reached, 0 if it hasn't reached one
+    pub fn drift_tier(&self) -> usize {
+        MINI_TURBO_TIERS
+            .iter()
+            .filter(|&&(charge, _)| self.drift_charge >= charge)
+            .count()
+    }
+
     pub fn new(x: f64, z: f64, spr_type: SpriteType) -> Kart {
         Kart {
             sprite: Sprite::new(x, z, spr_type)
@@ -47,12 +191,30 @@ impl Kart {
                 .set_framecount(8)
                 .set_rotation(std::f64::consts::PI / 2.0),
             knock_out: 0.0,
+            respawn_timer: 0.0,
+            last_valid_pos: (x, z),
             rotation_before_knockout: 0.0,
-            powerup: PowerupType::Empty,
+            powerup: None,
             laps: 0,
             current_checkpoint: 0,
+            position: 0,
+            lap_timer: LapTimer::new(),
+            items_used: 0,
+            knockouts: 0,
             powerup_amt: 0,
+            shielded: false,
+            shield_break_timer: 0.0,
             speed: 0.0,
+            bump_velocity: (0.0, 0.0),
+            stats: KartStats::default(),
+            prev_pos: (x, z),
+            wrong_way_timer: 0.0,
+            prev_checkpoint_dist: 0.0,
+            drift_direction: 0.0,
+            drift_charge: 0.0,
+            particle: None,
+            particle_timer: 0.0,
+            particle_left: false,
             max_speed: MAX_SPEED,
             friction: 0.0,
             acceleration: 0.0,
@@ -60,53 +222,158 @@ impl Kart {
         }
     }
 
+    pub fn set_stats(mut self, stats: KartStats) -> Self {
+        self.stats = stats;
+        self.max_speed = MAX_SPEED * stats.top_speed;
+        self.max_rotation_speed = stats.handling;
+        self
+    }
+
     //Apply friction and maximum speed to the kart
+    //based on the surface that it is driving on
     pub fn apply_friction(&mut self, level: &Level) {
-        let color = level.sample_color(self.sprite.trans_x, self.sprite.trans_z);
-        //Friction
-        if color[0] == 0 && color[1] >= 128 && color[2] == 0 {
-            //Green = grass, slippery
-            //set maximum speed as well
-            self.max_speed = 0.5;
-            self.friction = 0.1;
-        } else if color[0] == 0 && color[1] >= 128 && color[2] >= 128 {
-            self.max_speed = 6.0;
-            self.speed = 6.0;
-        } else {
-            //Everything else is road
-            self.max_speed = 3.0;
-            self.friction = 0.4;
+        let surface = level.surface_at(self.sprite.trans_x, self.sprite.trans_z);
+        self.max_speed = surface.max_speed * self.stats.top_speed;
+        self.friction = surface.friction;
+
+        if let Some(boost) = surface.boost {
+            self.speed = boost;
         }
+
+        if let Some(time) = surface.knockout {
+            self.knock_out_for(time);
+        }
+
+        self.particle = surface.particle;
     }
 
-    //Drives kart with keyboard
-    //acceleration key: acclerate forward
-    //left key: turn left
-    //right key: turn right
-    pub fn drive_kart(
-        &mut self,
-        events: &Events,
-        acceleration_key: Keycode,
-        left_key: Keycode,
-        right_key: Keycode,
-    ) {
-        //Accelerate kart
-        if events.key_is_pressed(acceleration_key) {
-            //Set kart's speed to be a minimum of 0.5
-            if self.speed < 0.5 {
+    //Returns a particle behind the kart if the surface that it is driving on
+    //kicks up particles, alternates between the left and right wheel
+    //drifting karts give off sparks once the drift has charged up instead
+    pub fn spawn_particle(&mut self, dt: f64) -> Option<Particle> {
+        let particle = match self.drift_tier() {
+            0 => self.particle?,
+            1 => SpriteType::SparkBlue,
+            _ => SpriteType::SparkOrange,
+        };
+        if self.speed < 0.5 || self.knocked_out() {
+            return None;
+        }
+
+        self.particle_timer += dt;
+        if self.particle_timer < PARTICLE_INTERVAL {
+            return None;
+        }
+        self.particle_timer = 0.0;
+        self.particle_left = !self.particle_left;
+
+        let side = if self.particle_left { 0.04 } else { -0.04 };
+        let (sin, cos) = self.sprite.rotation.sin_cos();
+        Some(Particle::new(
+            self.sprite.trans_x - sin * 0.08 - cos * side,
+            self.sprite.trans_z - cos * 0.08 + sin * side,
+            particle,
+        ))
+    }
+
+    fn start_drift(&mut self, direction: f64) {
+        self.drift_direction = direction;
+        self.drift_charge = 0.0;
+        self.sprite.drifting = true;
+    }
+
+    //Stops drifting without a mini-turbo
+    fn cancel_drift(&mut self) {
+        self.drift_direction = 0.0;
+        self.drift_charge = 0.0;
+        self.sprite.drifting = false;
+    }
+
+    //Stops drifting and gives the kart a boost if the drift was long enough
+    fn end_drift(&mut self) {
+        let tier = self.drift_tier();
+        if tier > 0 {
+            self.speed += MINI_TURBO_TIERS[tier - 1].1;
+        }
+        self.cancel_drift();
+    }
+
+    //Drifting starts when the drift button is held while turning and ends when
+    //the button is let go of, a drift that slows down too much (such as by
+    //hitting a wall) is lost without a mini-turbo
+    fn update_drift(&mut self, input: &KartInput) {
+        let can_drift = input.drift && !input.brake && self.speed >= DRIFT_MIN_SPEED;
+
+        if self.drifting() && self.speed < DRIFT_MIN_SPEED {
+            self.cancel_drift();
+        } else if self.drifting() && !can_drift {
+            self.end_drift();
+        } else if !self.drifting() && can_drift && input.left {
+            self.start_drift(-1.0);
+        } else if !self.drifting() && can_drift && input.right {
+            self.start_drift(1.0);
+        }
+    }
+
+    //Stops the kart where it is (once it has finished the race)
+    pub fn stop(&mut self) {
+        self.speed = 0.0;
+        self.sprite.rotation_speed = 0.0;
+        self.cancel_drift();
+    }
+
+    //Drives kart with the input from the keyboard or the computer
+    pub fn drive_kart(&mut self, input: &KartInput) {
+        self.update_drift(input);
+
+        //Brake and once the kart has stopped, reverse
+        if input.brake {
+            self.acceleration = if self.speed > 0.0 {
+                -BRAKE_DECELERATION
+            } else {
+                -REVERSE_ACCELERATION
+            };
+        } else if input.accelerate {
+            //Set kart's speed to be a minimum of 0.5,
+            //a reversing kart has to slow down first
+            if (0.0..0.5).contains(&self.speed) {
                 self.speed = 0.5;
             }
 
-            self.acceleration = 0.7;
+            self.acceleration = self.stats.acceleration;
         } else {
             //Stop accelecration once key is released
             self.acceleration = 0.0;
         }
 
-        //Rotate left and rotate right
-        if events.key_is_pressed(left_key) {
+        //A drifting kart always turns into the drift,
+        //steering only changes how tight the turn is
+        if self.drifting() {
+            let steering = if input.left {
+                -self.drift_direction
+            } else if input.right {
+                self.drift_direction
+            } else {
+                0.0
+            };
+            let scale = if steering > 0.0 {
+                DRIFT_TURN_IN
+            } else if steering < 0.0 {
+                DRIFT_TURN_OUT
+            } else {
+                DRIFT_TURN
+            };
+            self.max_rotation_speed = self.stats.handling * scale;
+            self.sprite.rotation_speed = self.drift_direction * self.speed * scale;
+            return;
+        }
+        self.max_rotation_speed = self.stats.handling;
+
+        //Rotate left and rotate right, when reversing the speed is negative
+        //so the kart turns the other way like a car backing up
+        if input.left {
             self.sprite.rotation_speed = -self.speed;
-        } else if events.key_is_pressed(right_key) {
+        } else if input.right {
             self.sprite.rotation_speed = self.speed;
         } else {
             //None of these keys pressed, don't rotate
@@ -115,7 +382,19 @@ impl Kart {
     }
 
     //Move the kart
-    pub fn move_kart(&mut self, dt: f64) {
+    pub fn move_kart(&mut self, dt: f64, level: &Level) {
+        self.shield_break_timer = (self.shield_break_timer - dt).max(0.0);
+
+        if self.respawning() || self.knocked_out() {
+            self.cancel_drift();
+        }
+
+        if self.respawning() {
+            self.bump_velocity = (0.0, 0.0);
+            self.update_respawn(dt, level);
+            return;
+        }
+
         //Knocked out
         if self.knocked_out() {
             self.knock_out -= dt;
@@ -139,54 +418,287 @@ impl Kart {
         }
 
         self.rotation_before_knockout = self.sprite.rotation;
-        self.update(dt);
+        self.update(dt, level);
     }
 
-    fn update(&mut self, dt: f64) {
+    //Fades the kart out, puts it back at the last place it was on the road
+    //facing its next checkpoint and then fades it back in
+    fn update_respawn(&mut self, dt: f64, level: &Level) {
+        let halfway = RESPAWN_TIME / 2.0;
+        let faded_out = self.respawn_timer <= halfway;
+        self.respawn_timer -= dt;
+        self.acceleration = 0.0;
+        self.speed = 0.0;
+        self.sprite.rotation_speed = 0.0;
+
+        if !faded_out && self.respawn_timer <= halfway {
+            let (x, z) = self.last_valid_pos;
+            let (target_x, target_z) = level.checkpoint_position(self.current_checkpoint);
+            self.sprite.trans_x = x;
+            self.sprite.trans_z = z;
+            self.sprite.rotation = (target_x - x)
+                .atan2(target_z - z)
+                .rem_euclid(std::f64::consts::PI * 2.0);
+            self.rotation_before_knockout = self.sprite.rotation;
+            //Don't count the jump back onto the road as going through a checkpoint
+            self.prev_pos = (x, z);
+        }
+
+        let fade = ((self.respawn_timer - halfway).abs() / halfway).min(1.0);
+        self.sprite.alpha = (fade * 255.0) as u8;
+    }
+
+    //Returns 1 if the kart is going forward and -1 if it is reversing
+    fn direction(&self) -> f64 {
+        if self.speed < 0.0 {
+            -1.0
+        } else {
+            1.0
+        }
+    }
+
+    //Direction that the kart is moving in, a drifting kart
+    //slides towards the outside of the turn
+    fn travel_angle(&self) -> f64 {
+        self.sprite.rotation - self.drift_direction * DRIFT_SLIDE_ANGLE
+    }
+
+    //Returns true if the kart would hit a wall at the position,
+    //walls are checked behind the kart when it is reversing
+    fn hits_wall(&self, x: f64, z: f64, level: &Level) -> bool {
+        let (sin, cos) = self.travel_angle().sin_cos();
+        let dist = WALL_CHECK_DIST * self.direction();
+        level.solid_at(x + sin * dist, z + cos * dist)
+    }
+
+    fn update(&mut self, dt: f64, level: &Level) {
         if self.speed > self.max_speed {
             self.acceleration = -(self.speed - self.max_speed) * 0.5;
         }
 
-        self.speed += (self.acceleration - self.friction) * dt;
+        //Friction slows the kart down whichever way it is going
+        let prev_speed = self.speed;
+        self.speed += (self.acceleration - self.friction * self.direction()) * dt;
 
         let min = -self.max_rotation_speed;
         let max = self.max_rotation_speed;
         self.sprite.rotation_speed = self.sprite.rotation_speed.clamp(min, max);
 
-        if self.speed < 0.0 {
+        //Stop instead of going the other way when braking or when friction
+        //slows the kart down, the kart only starts reversing once it has stopped
+        let changed_direction = prev_speed * self.speed < 0.0;
+        //Friction can't get a stopped kart moving, only accelerating or reversing can
+        let pushed_by_friction = prev_speed == 0.0 && self.speed * self.acceleration <= 0.0;
+        if (changed_direction && (prev_speed > 0.0 || self.acceleration <= 0.0))
+            || pushed_by_friction
+        {
             self.speed = 0.0;
         }
 
-        self.sprite.trans_x += self.sprite.rotation.sin() * self.speed * dt;
-        self.sprite.trans_z += self.sprite.rotation.cos() * self.speed * dt;
+        let max_reverse_speed = self.max_speed * REVERSE_SPEED_SCALE;
+        if self.speed < -max_reverse_speed {
+            self.speed = -max_reverse_speed;
+        }
+
+        if self.drifting() {
+            self.drift_charge += dt;
+        }
+
+        let (x, z) = (self.sprite.trans_x, self.sprite.trans_z);
+        let (sin, cos) = self.travel_angle().sin_cos();
+        let (bump_x, bump_z) = self.bump_velocity;
+        let new_x = x + (sin * self.speed + bump_x) * dt;
+        let new_z = z + (cos * self.speed + bump_z) * dt;
+        let damping = BUMP_DAMPING.powf(dt);
+        self.bump_velocity = (bump_x * damping, bump_z * damping);
+
+        if !self.hits_wall(new_x, new_z, level) {
+            self.sprite.trans_x = new_x;
+            self.sprite.trans_z = new_z;
+        } else {
+            //Walls stop karts from being pushed any further
+            self.bump_velocity = (0.0, 0.0);
+
+            if !self.hits_wall(new_x, z, level) {
+                //Slide along the wall
+                self.sprite.trans_x = new_x;
+                self.speed *= WALL_SPEED_PENALTY.powf(dt);
+            } else if !self.hits_wall(x, new_z, level) {
+                self.sprite.trans_z = new_z;
+                self.speed *= WALL_SPEED_PENALTY.powf(dt);
+            } else if self.speed != 0.0 {
+                //Drove straight into the wall, bounce back unless there is a wall there too
+                let bounce = WALL_BOUNCE_DIST * self.direction();
+                self.nudge(-sin * bounce, -cos * bounce, level);
+                self.speed *= WALL_SPEED_PENALTY;
+            }
+        }
+
         self.sprite.rotation += self.sprite.rotation_speed * dt;
+
+        //Check if the kart left the track
+        let (x, z) = (self.sprite.trans_x, self.sprite.trans_z);
+        if !level.in_bounds(x, z) || level.surface_at(x, z).pit {
+            self.respawn_timer = RESPAWN_TIME;
+        } else if level.surface_at(x, z).road {
+            self.last_valid_pos = (x, z);
+        }
     }
 
-    pub fn moving(&self) -> bool {
-        self.sprite.rotation_speed != 0.0 || self.speed != 0.0
+    //Speed of the kart along the x and z axes
+    fn velocity(&self) -> (f64, f64) {
+        let (sin, cos) = self.travel_angle().sin_cos();
+        let (bump_x, bump_z) = self.bump_velocity;
+        (sin * self.speed + bump_x, cos * self.speed + bump_z)
     }
 
-    pub fn pickup_powerup(&mut self) {
-        self.powerup_amt = rand::random::<u16>() % 3 + 1;
-        self.powerup = POWERUP_TYPES[rand::random::<usize>() % 3];
+    //Splits the velocity into speed in the direction that the
+    //kart is going and sideways speed from being bumped
+    fn set_velocity(&mut self, (vel_x, vel_z): (f64, f64)) {
+        let (sin, cos) = self.travel_angle().sin_cos();
+        self.speed = vel_x * sin + vel_z * cos;
+        self.bump_velocity = (vel_x - sin * self.speed, vel_z - cos * self.speed);
     }
 
-    pub fn use_powerup(&mut self) -> PowerupType {
-        if self.knocked_out() {
-            return PowerupType::Empty;
+    //Moves the kart unless that would put it inside a wall
+    fn nudge(&mut self, dx: f64, dz: f64, level: &Level) {
+        let (x, z) = (self.sprite.trans_x + dx, self.sprite.trans_z + dz);
+        if level.in_bounds(x, z) && !level.solid_at(x, z) {
+            self.sprite.trans_x = x;
+            self.sprite.trans_z = z;
+        }
+    }
+
+    //If the karts are touching, pushes them apart so that they don't overlap
+    //and bounces them off each other, lighter karts get pushed further
+    pub fn bump(&mut self, other: &mut Kart, level: &Level) {
+        if self.respawning() || other.respawning() {
+            return;
+        }
+
+        let dist = dist_between(&self.sprite, &other.sprite);
+        if dist >= KART_RADIUS * 2.0 || dist == 0.0 {
+            return;
+        }
+
+        //Direction from this kart to the other one
+        let (norm_x, norm_z) = xz_diff_norm(&other.sprite, &self.sprite);
+        let (weight1, weight2) = (self.stats.weight, other.stats.weight);
+        let overlap = KART_RADIUS * 2.0 - dist;
+        let push = overlap * weight2 / (weight1 + weight2);
+        self.nudge(-norm_x * push, -norm_z * push, level);
+        let push = overlap * weight1 / (weight1 + weight2);
+        other.nudge(norm_x * push, norm_z * push, level);
+
+        //Only bounce if the karts are moving towards each other
+        let (vel1, vel2) = (self.velocity(), other.velocity());
+        let closing = (vel1.0 - vel2.0) * norm_x + (vel1.1 - vel2.1) * norm_z;
+        if closing <= 0.0 {
+            return;
+        }
+
+        let impulse = (1.0 + BUMP_RESTITUTION) * closing / (1.0 / weight1 + 1.0 / weight2);
+        self.set_velocity((
+            vel1.0 - impulse / weight1 * norm_x,
+            vel1.1 - impulse / weight1 * norm_z,
+        ));
+        other.set_velocity((
+            vel2.0 + impulse / weight2 * norm_x,
+            vel2.1 + impulse / weight2 * norm_z,
+        ));
+    }
+
+    //Checks if the kart drove through its next checkpoint since the last time
+    //this was called, returns true if the kart reached a new checkpoint
+    //time is the race time, used to time the kart's laps
+    pub fn update_checkpoint(&mut self, level: &Level, time: f64) -> bool {
+        let pos = (self.sprite.trans_x, self.sprite.trans_z);
+        let mut reached_checkpoint = false;
+        let last_checkpoint = level.checkpoints.len() - 1;
+        //When a lap starts and ends at the same gate, going through it as the first
+        //checkpoint only starts the lap so it isn't timed as a split
+        let start_is_finish =
+            last_checkpoint > 0 && level.checkpoints[0] == level.checkpoints[last_checkpoint];
+
+        //A fast kart can go through more than one gate in a single step
+        for _ in 0..level.checkpoints.len() {
+            if !level.crossed_checkpoint(self.current_checkpoint, self.prev_pos, pos) {
+                break;
+            }
+
+            let lap_finished = self.current_checkpoint == last_checkpoint;
+            if lap_finished {
+                self.laps += 1;
+            }
+            if self.current_checkpoint != 0 || !start_is_finish {
+                self.lap_timer.checkpoint(time, lap_finished);
+            }
+
+            self.current_checkpoint += 1;
+            self.current_checkpoint %= level.checkpoints.len();
+            reached_checkpoint = true;
+        }
+
+        self.prev_pos = pos;
+        reached_checkpoint
+    }
+
+    //Keeps track of how long the kart has been facing and moving away
+    //from its next checkpoint
+    pub fn update_wrong_way(&mut self, level: &Level, dt: f64) {
+        let (target_x, target_z) = level.checkpoint_position(self.current_checkpoint);
+        let xdiff = target_x - self.sprite.trans_x;
+        let zdiff = target_z - self.sprite.trans_z;
+        let dist = (xdiff * xdiff + zdiff * zdiff).sqrt();
+        //Negative if the kart is facing away from the checkpoint
+        let facing = self.sprite.rotation.sin() * xdiff + self.sprite.rotation.cos() * zdiff;
+
+        let spinning = self.knocked_out() || self.respawning();
+        if facing < 0.0 && dist > self.prev_checkpoint_dist && !spinning {
+            self.wrong_way_timer += dt;
+        } else if dist < self.prev_checkpoint_dist || spinning {
+            self.wrong_way_timer = 0.0;
         }
 
-        if self.powerup == PowerupType::Empty {
-            self.powerup_amt = 0;
-            return PowerupType::Empty;
+        self.prev_checkpoint_dist = dist;
+    }
+
+    pub fn wrong_way(&self) -> bool {
+        self.wrong_way_timer > WRONG_WAY_TIME
+    }
+
+    pub fn moving(&self) -> bool {
+        self.sprite.rotation_speed != 0.0 || self.speed != 0.0 || self.respawning()
+    }
+
+    pub fn pickup_powerup(&mut self, item: ItemId, amount: u16) {
+        self.powerup = Some(item);
+        self.powerup_amt = amount;
+    }
+
+    //Takes one of the items that the kart is holding
+    pub fn use_powerup(&mut self) -> Option<ItemId> {
+        if self.knocked_out() || self.powerup_amt == 0 {
+            return None;
         }
 
-        if self.powerup_amt > 0 {
-            self.powerup_amt -= 1;
-            return self.powerup;
+        let item = self.powerup?;
+        self.powerup_amt -= 1;
+        self.items_used += 1;
+        if self.powerup_amt == 0 {
+            self.powerup = None;
         }
 
-        self.powerup = PowerupType::Empty;
-        PowerupType::Empty
+        Some(item)
+    }
+}
+
+//Bumps every pair of karts that are touching
+pub fn bump_karts(karts: &mut [&mut Kart], level: &Level) {
+    for i in 0..karts.len() {
+        let (first, rest) = karts.split_at_mut(i + 1);
+        for other in rest {
+            first[i].bump(other, level);
+        }
     }
 }
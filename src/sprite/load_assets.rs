@@ -1,4 +1,5 @@
-use crate::sprite::kart::PowerupType;
+use crate::character::Character;
+use crate::item::registry::ITEMS;
 use crate::sprite::SpriteType;
 use sdl2::pixels::PixelFormatEnum;
 use sdl2::render::{BlendMode, Texture, TextureCreator};
@@ -56,6 +57,27 @@ pub fn load_texture<'a>(
     }
 }
 
+//Sprites of the karts in a race, the players' karts come first
+pub const KART_SLOTS: [SpriteType; 4] = [
+    SpriteType::Kart1,
+    SpriteType::Kart2,
+    SpriteType::Kart3,
+    SpriteType::Kart4,
+];
+
+//Replaces the kart sprites with the sprite sheets of the karts in the race
+pub fn load_kart_assets<'a>(
+    sprite_images: &mut HashMap<SpriteType, Texture<'a>>,
+    racers: &[&Character],
+    texture_creator: &'a TextureCreator<WindowContext>,
+) -> Result<(), String> {
+    for (&slot, racer) in KART_SLOTS.iter().zip(racers) {
+        sprite_images.insert(slot, load_texture(&racer.sprite, texture_creator)?);
+    }
+
+    Ok(())
+}
+
 pub fn load_assets<'a>(
     texture_creator: &'a TextureCreator<WindowContext>,
 ) -> Result<HashMap<SpriteType, Texture<'a>>, String> {
@@ -71,6 +93,16 @@ pub fn load_assets<'a>(
         load_texture("assets/images/kart2.png", texture_creator)?,
     );
 
+    sprite_images.insert(
+        SpriteType::Kart3,
+        load_texture("assets/images/kart3.png", texture_creator)?,
+    );
+
+    sprite_images.insert(
+        SpriteType::Kart4,
+        load_texture("assets/images/kart4.png", texture_creator)?,
+    );
+
     sprite_images.insert(
         SpriteType::Checkpoint1,
         load_texture("assets/images/kart-checkpoint1.png", texture_creator)?,
@@ -81,11 +113,6 @@ pub fn load_assets<'a>(
         load_texture("assets/images/kart-checkpoint2.png", texture_creator)?,
     );
 
-    sprite_images.insert(
-        SpriteType::Banana,
-        load_texture("assets/images/enemies/banana.png", texture_creator)?,
-    );
-
     sprite_images.insert(
         SpriteType::Powerup,
         load_texture("assets/images/powerups/powerupblock.png", texture_creator)?,
@@ -97,32 +124,40 @@ pub fn load_assets<'a>(
     );
 
     sprite_images.insert(
-        SpriteType::Fireball,
-        load_texture("assets/images/enemies/fireball.png", texture_creator)?,
+        SpriteType::Dust,
+        load_texture("assets/images/particles/dust.png", texture_creator)?,
     );
 
-    Ok(sprite_images)
-}
-
-pub fn load_powerup_icon_assets<'a>(
-    texture_creator: &'a TextureCreator<WindowContext>,
-) -> Result<HashMap<PowerupType, Texture<'a>>, String> {
-    let mut powerup_icons = HashMap::<PowerupType, Texture<'a>>::new();
-
-    powerup_icons.insert(
-        PowerupType::Banana,
-        load_texture("assets/images/powerups/bananaicon.png", texture_creator)?,
+    sprite_images.insert(
+        SpriteType::Sparkle,
+        load_texture("assets/images/particles/sparkle.png", texture_creator)?,
     );
 
-    powerup_icons.insert(
-        PowerupType::SpeedBoost,
-        load_texture("assets/images/powerups/speedboosticon.png", texture_creator)?,
+    sprite_images.insert(
+        SpriteType::SparkBlue,
+        load_texture("assets/images/particles/spark-blue.png", texture_creator)?,
     );
 
-    powerup_icons.insert(
-        PowerupType::Fireball,
-        load_texture("assets/images/powerups/fireballicon.png", texture_creator)?,
+    sprite_images.insert(
+        SpriteType::SparkOrange,
+        load_texture("assets/images/particles/spark-orange.png", texture_creator)?,
     );
 
-    Ok(powerup_icons)
+    for (i, item) in ITEMS.iter().enumerate() {
+        if let Some(path) = item.sprite {
+            sprite_images.insert(SpriteType::Item(i), load_texture(path, texture_creator)?);
+        }
+    }
+
+    Ok(sprite_images)
+}
+
+//Icons of the items in the same order as ITEMS
+pub fn load_powerup_icon_assets<'a>(
+    texture_creator: &'a TextureCreator<WindowContext>,
+) -> Result<Vec<Texture<'a>>, String> {
+    ITEMS
+        .iter()
+        .map(|item| load_texture(item.icon, texture_creator))
+        .collect()
 }
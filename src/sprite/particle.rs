@@ -0,0 +1,30 @@
+use crate::sprite::{Sprite, SpriteType};
+
+const PARTICLE_LIFETIME: f64 = 0.4;
+const PARTICLE_SIZE: f64 = 0.05;
+
+//Small sprites that shrink and disappear after a short amount of time
+pub struct Particle {
+    pub sprite: Sprite,
+    timer: f64,
+}
+
+impl Particle {
+    pub fn new(x: f64, z: f64, spr_type: SpriteType) -> Particle {
+        Particle {
+            sprite: Sprite::new(x, z, spr_type).set_size(PARTICLE_SIZE, PARTICLE_SIZE),
+            timer: PARTICLE_LIFETIME,
+        }
+    }
+
+    pub fn update(&mut self, dt: f64) {
+        self.timer -= dt;
+        let size = PARTICLE_SIZE * (self.timer / PARTICLE_LIFETIME).max(0.0);
+        self.sprite.width = size;
+        self.sprite.height = size;
+    }
+
+    pub fn done(&self) -> bool {
+        self.timer <= 0.0
+    }
+}
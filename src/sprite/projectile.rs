@@ -0,0 +1,94 @@
+use crate::item::registry::{ItemId, ItemUse};
+use crate::level::Level;
+use crate::sprite::{Sprite, SpriteType};
+
+//An item that has been used and is on the track
+pub struct Projectile {
+    pub sprite: Sprite,
+    pub item: ItemId,
+    pub owner: usize,          //Index of the kart that used the item
+    pub target: Option<usize>, //Index of the kart that the item chases or follows
+    pub timer: f64,
+    direction: (f64, f64),
+    bounces: u32, //Number of times the item has bounced off a wall
+}
+
+impl Projectile {
+    pub fn new(
+        (x, z): (f64, f64),
+        item: ItemId,
+        owner: usize,
+        direction: (f64, f64),
+        target: Option<usize>,
+    ) -> Self {
+        let size = item.kind().size;
+
+        Self {
+            sprite: Sprite::new(x, z, SpriteType::Item(item.0)).set_size(size, size),
+            item,
+            owner,
+            target,
+            timer: 0.0,
+            direction,
+            bounces: 0,
+        }
+    }
+
+    //Turns towards the target
+    fn chase(&mut self, target: &Sprite) {
+        let xdiff = target.trans_x - self.sprite.trans_x;
+        let zdiff = target.trans_z - self.sprite.trans_z;
+        let dist = (xdiff * xdiff + zdiff * zdiff).sqrt();
+
+        if dist > 0.0 {
+            self.direction = (xdiff / dist, zdiff / dist);
+        }
+    }
+
+    //Flips the direction of the item if it is about to fly into a wall
+    fn bounce(&mut self, dx: f64, dz: f64, level: &Level) {
+        let (x, z) = (self.sprite.trans_x, self.sprite.trans_z);
+        let blocked = |x, z| !level.in_bounds(x, z) || level.solid_at(x, z);
+
+        if blocked(x + dx, z) {
+            self.direction.0 = -self.direction.0;
+            self.bounces += 1;
+        }
+
+        if blocked(x, z + dz) {
+            self.direction.1 = -self.direction.1;
+            self.bounces += 1;
+        }
+    }
+
+    pub fn update(&mut self, dt: f64, target: Option<&Sprite>, level: &Level) {
+        self.timer += dt;
+        let speed = self.item.kind().speed;
+
+        match (self.item.kind().usage, target) {
+            (ItemUse::Homing, Some(target)) => self.chase(target),
+            (ItemUse::Shield, Some(target)) => {
+                self.sprite.trans_x = target.trans_x;
+                self.sprite.trans_z = target.trans_z;
+            }
+            (ItemUse::Thrown { .. }, _) => self.bounce(
+                self.direction.0 * speed * dt,
+                self.direction.1 * speed * dt,
+                level,
+            ),
+            _ => {}
+        }
+
+        self.sprite.trans_x += self.direction.0 * speed * dt;
+        self.sprite.trans_z += self.direction.1 * speed * dt;
+    }
+
+    pub fn expired(&self) -> bool {
+        let kind = self.item.kind();
+
+        match kind.usage {
+            ItemUse::Thrown { bounces } if self.bounces > bounces => true,
+            _ => self.timer > kind.lifetime,
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use sdl2::{
+    keyboard::Keycode,
+    pixels::Color,
+    render::{Canvas, TextureCreator},
+    ttf::Font,
+    video::{Window, WindowContext},
+};
+
+use crate::{
+    events::Events,
+    grandprix::GrandPrix,
+    level::Level,
+    menu::{Button, Text},
+    race,
+};
+
+//What the player picked on the standings screen
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum StandingsChoice {
+    Continue,
+    WatchReplay,
+    SaveReplay,
+}
+
+//Shows the points table between the races of a grand prix
+//and the winner of the cup once every race is done
+pub struct StandingsScreen {
+    seed: u64,                   //Seed of the race that was just finished
+    replay_path: Option<String>, //Where the replay of the race was saved
+    continue_button: Button,
+    watch_button: Button,
+    save_replay_button: Button,
+}
+
+impl StandingsScreen {
+    pub fn init(seed: u64) -> Self {
+        Self {
+            seed,
+            replay_path: None,
+            continue_button: Button::new(0, 208, "Continue"),
+            watch_button: Button::new(-300, 208, "Watch Replay"),
+            save_replay_button: Button::new(300, 208, "Save Replay"),
+        }
+    }
+
+    pub fn replay_saved(&mut self, path: String) {
+        self.replay_path = Some(path);
+    }
+
+    pub fn press_buttons(
+        &self,
+        events: &mut Events,
+        canvas_dimensions: (u32, u32),
+    ) -> Option<StandingsChoice> {
+        if self.continue_button.clicked(events, canvas_dimensions)
+            || events.key_is_pressed_once(Keycode::Return)
+        {
+            Some(StandingsChoice::Continue)
+        } else if self.watch_button.clicked(events, canvas_dimensions) {
+            Some(StandingsChoice::WatchReplay)
+        } else if self.replay_path.is_none()
+            && self.save_replay_button.clicked(events, canvas_dimensions)
+        {
+            Some(StandingsChoice::SaveReplay)
+        } else {
+            None
+        }
+    }
+
+    pub fn display(
+        &self,
+        canvas: &mut Canvas<Window>,
+        texture_creator: &TextureCreator<WindowContext>,
+        events: &Events,
+        font: &Font,
+        grand_prix: &GrandPrix,
+        tracks: &[Level],
+    ) -> Result<(), String> {
+        let (canv_w, canv_h) = canvas.output_size()?;
+        let center_x = canv_w as i32 / 2;
+        let standings = grand_prix.standings();
+
+        let title = if grand_prix.finished() {
+            format!("{} won the cup!", grand_prix.racer_names[standings[0].0])
+        } else {
+            String::from("Grand Prix Standings")
+        };
+        let title_text = Text::new(title.as_str(), center_x, 32, Color::WHITE, 32);
+        title_text.display_center(canvas, texture_creator, font)?;
+
+        let round_text = Text::new(
+            format!(
+                "Round {} of {}",
+                grand_prix.round(),
+                grand_prix.round_count()
+            )
+            .as_str(),
+            center_x,
+            104,
+            Color::WHITE,
+            16,
+        );
+        round_text.display_center(canvas, texture_creator, font)?;
+
+        if !grand_prix.finished() {
+            let next_text = Text::new(
+                format!("Next: {}", tracks[grand_prix.current_track()].name).as_str(),
+                center_x,
+                canv_h as i32 / 2 + 120,
+                Color::WHITE,
+                16,
+            );
+            next_text.display_center(canvas, texture_creator, font)?;
+        }
+
+        //Display the table of points
+        for (i, &(racer, points, last_points)) in standings.iter().enumerate() {
+            let y = canv_h as i32 / 2 - 112 + i as i32 * 48;
+            //Highlight the player
+            let color = if racer == 0 {
+                Color::RGB(255, 128, 0)
+            } else {
+                Color::WHITE
+            };
+
+            let place_text = Text::new(race::ordinal(i + 1).as_str(), center_x - 288, y, color, 24);
+            place_text.display_left_justify(canvas, texture_creator, font)?;
+
+            let name_text = Text::new(&grand_prix.racer_names[racer], center_x - 192, y, color, 24);
+            name_text.display_left_justify(canvas, texture_creator, font)?;
+
+            let points_text = Text::new(
+                format!("{points} (+{last_points})").as_str(),
+                center_x + 288,
+                y,
+                color,
+                24,
+            );
+            points_text.display_right_justify(canvas, texture_creator, font)?;
+        }
+
+        //The seed lets the race be played again with --seed
+        let seed_text = Text::new(
+            format!("Seed: {}", self.seed).as_str(),
+            16,
+            16,
+            Color::WHITE,
+            8,
+        );
+        seed_text.display_left_justify(canvas, texture_creator, font)?;
+
+        if let Some(path) = &self.replay_path {
+            let replay_text = Text::new(
+                format!("Replay saved: {path}").as_str(),
+                16,
+                40,
+                Color::WHITE,
+                8,
+            );
+            replay_text.display_left_justify(canvas, texture_creator, font)?;
+        }
+
+        self.continue_button
+            .display(canvas, texture_creator, events, font)?;
+        self.watch_button
+            .display(canvas, texture_creator, events, font)?;
+        if self.replay_path.is_none() {
+            self.save_replay_button
+                .display(canvas, texture_creator, events, font)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,235 @@
+use sdl2::{
+    keyboard::Keycode,
+    pixels::{Color, PixelFormatEnum},
+    rect::{Point, Rect},
+    render::{BlendMode, Canvas, Texture, TextureCreator},
+    ttf::Font,
+    video::{Window, WindowContext},
+};
+
+use crate::{
+    events::Events,
+    item::registry::ItemOdds,
+    level::Level,
+    menu::{Button, Text},
+    race::RaceRules,
+    sprite::ai_kart::Difficulty,
+    GameScreen,
+};
+
+const THUMBNAIL_SIZE: usize = 128;
+const THUMBNAIL_DISPLAY_SIZE: u32 = 192;
+
+//Creates a top down thumbnail texture for each track
+pub fn create_thumbnails<'a>(
+    tracks: &[Level],
+    texture_creator: &'a TextureCreator<WindowContext>,
+) -> Result<Vec<Texture<'a>>, String> {
+    let mut thumbnails = vec![];
+
+    for track in tracks {
+        let (pixels, w, h) = track.create_thumbnail(THUMBNAIL_SIZE);
+        let mut tex = texture_creator
+            .create_texture_static(PixelFormatEnum::ARGB8888, w as u32, h as u32)
+            .map_err(|e| e.to_string())?;
+        tex.set_blend_mode(BlendMode::Blend);
+        tex.update(None, &pixels, w * 4)
+            .map_err(|e| e.to_string())?;
+        thumbnails.push(tex);
+    }
+
+    Ok(thumbnails)
+}
+
+pub struct TrackSelectScreen {
+    pub selected: usize,
+    pub difficulty: Difficulty, //Difficulty of the computer karts in single player
+    pub rules: RaceRules,
+    mode: GameScreen,  //The screen to go to once a track is picked
+    laps_chosen: bool, //Has the player changed the lap count
+    difficulty_button: Button,
+    laps_button: Button,
+    countdown_button: Button,
+    items_button: Button,
+    enemies_button: Button,
+    prev_button: Button,
+    next_button: Button,
+    start_button: Button,
+    back_button: Button,
+}
+
+impl TrackSelectScreen {
+    pub fn init(race_mode: GameScreen, tracks: &[Level]) -> Self {
+        let mut rules = RaceRules::new(&tracks[0]);
+        if race_mode == GameScreen::TimeTrial {
+            rules.items = ItemOdds::Off;
+        }
+
+        let mut track_select = Self {
+            selected: 0,
+            difficulty: Difficulty::Normal,
+            rules,
+            mode: race_mode,
+            laps_chosen: false,
+            difficulty_button: Button::new(220, 144, ""),
+            laps_button: Button::new(-220, 104, ""),
+            countdown_button: Button::new(-220, 144, ""),
+            items_button: Button::new(220, 104, ""),
+            enemies_button: Button::new(-220, 184, ""),
+            prev_button: Button::new(-176, -16, "<"),
+            next_button: Button::new(176, -16, ">"),
+            start_button: Button::new(160, 240, "Start"),
+            back_button: Button::new(-160, 240, "Back"),
+        };
+        track_select.update_button_text();
+        track_select
+    }
+
+    fn update_button_text(&mut self) {
+        let on_off = |on: bool| if on { "On" } else { "Off" };
+        self.difficulty_button.text = format!("Difficulty: {}", self.difficulty.name());
+        self.laps_button.text = format!("Laps: {}", self.rules.laps);
+        self.countdown_button.text = format!("Countdown: {}s", self.rules.countdown);
+        self.items_button.text = format!("Items: {}", self.rules.items.name());
+        self.enemies_button.text = format!("Enemies: {}", on_off(self.rules.enemies));
+    }
+
+    //Picks a track, the lap count follows the track
+    //until the player picks their own
+    fn select_track(&mut self, index: usize, tracks: &[Level]) {
+        self.selected = index;
+        if !self.laps_chosen {
+            self.rules.laps = RaceRules::new(&tracks[index]).laps;
+        }
+        self.update_button_text();
+    }
+
+    //Only single player races have computer karts
+    fn shows_difficulty(&self) -> bool {
+        self.mode == GameScreen::OnePlayer || self.mode == GameScreen::GrandPrix
+    }
+
+    //A kart racing alone would always roll the boosts meant for last
+    //place, so time trial records and ghosts are set without items
+    fn shows_items(&self) -> bool {
+        self.mode != GameScreen::TimeTrial
+    }
+
+    pub fn press_buttons(
+        &mut self,
+        events: &mut Events,
+        canvas_dimensions: (u32, u32),
+        tracks: &[Level],
+    ) -> Option<GameScreen> {
+        let track_count = tracks.len();
+
+        if self.prev_button.clicked(events, canvas_dimensions)
+            || events.key_is_pressed_once(Keycode::Left)
+        {
+            self.select_track((self.selected + track_count - 1) % track_count, tracks);
+        } else if self.next_button.clicked(events, canvas_dimensions)
+            || events.key_is_pressed_once(Keycode::Right)
+        {
+            self.select_track((self.selected + 1) % track_count, tracks);
+        } else if self.shows_difficulty()
+            && (self.difficulty_button.clicked(events, canvas_dimensions)
+                || events.key_is_pressed_once(Keycode::Up))
+        {
+            self.difficulty = self.difficulty.next();
+            self.update_button_text();
+        } else if self.shows_difficulty() && events.key_is_pressed_once(Keycode::Down) {
+            self.difficulty = self.difficulty.prev();
+            self.update_button_text();
+        } else if self.laps_button.clicked(events, canvas_dimensions) {
+            self.rules.next_lap_count();
+            self.laps_chosen = true;
+            self.update_button_text();
+        } else if self.countdown_button.clicked(events, canvas_dimensions) {
+            self.rules.next_countdown();
+            self.update_button_text();
+        } else if self.shows_items() && self.items_button.clicked(events, canvas_dimensions) {
+            self.rules.items = self.rules.items.next();
+            self.update_button_text();
+        } else if self.enemies_button.clicked(events, canvas_dimensions) {
+            self.rules.enemies = !self.rules.enemies;
+            self.update_button_text();
+        } else if self.start_button.clicked(events, canvas_dimensions)
+            || events.key_is_pressed_once(Keycode::Return)
+        {
+            return Some(self.mode);
+        } else if self.back_button.clicked(events, canvas_dimensions)
+            || events.key_is_pressed_once(Keycode::Escape)
+        {
+            return Some(GameScreen::CharacterSelect);
+        }
+
+        None
+    }
+
+    pub fn display(
+        &self,
+        canvas: &mut Canvas<Window>,
+        texture_creator: &TextureCreator<WindowContext>,
+        events: &Events,
+        font: &Font,
+        tracks: &[Level],
+        thumbnails: &[Texture],
+    ) -> Result<(), String> {
+        let (canv_w, canv_h) = canvas.output_size()?;
+
+        //A grand prix goes through every track starting with the selected one
+        let title = match self.mode {
+            GameScreen::GrandPrix => "Select First Track",
+            _ => "Select Track",
+        };
+        let title_text = Text::new(title, canv_w as i32 / 2, 32, Color::WHITE, 32);
+        title_text.display_center(canvas, texture_creator, font)?;
+
+        let name_text = Text::new(
+            tracks[self.selected].name.as_str(),
+            canv_w as i32 / 2,
+            canv_h as i32 / 2 - 176,
+            Color::WHITE,
+            24,
+        );
+        name_text.display_center(canvas, texture_creator, font)?;
+
+        //Display the thumbnail, scaled to fit in a square
+        let thumbnail = &thumbnails[self.selected];
+        let query = thumbnail.query();
+        let scale = THUMBNAIL_DISPLAY_SIZE as f64 / query.width.max(query.height) as f64;
+        let thumbnail_rect = Rect::from_center(
+            Point::new(canv_w as i32 / 2, canv_h as i32 / 2 - 24),
+            (query.width as f64 * scale) as u32,
+            (query.height as f64 * scale) as u32,
+        );
+        canvas
+            .copy(thumbnail, None, thumbnail_rect)
+            .map_err(|e| e.to_string())?;
+
+        self.prev_button
+            .display(canvas, texture_creator, events, font)?;
+        self.next_button
+            .display(canvas, texture_creator, events, font)?;
+        if self.shows_difficulty() {
+            self.difficulty_button
+                .display(canvas, texture_creator, events, font)?;
+        }
+        self.laps_button
+            .display(canvas, texture_creator, events, font)?;
+        self.countdown_button
+            .display(canvas, texture_creator, events, font)?;
+        if self.shows_items() {
+            self.items_button
+                .display(canvas, texture_creator, events, font)?;
+        }
+        self.enemies_button
+            .display(canvas, texture_creator, events, font)?;
+        self.start_button
+            .display(canvas, texture_creator, events, font)?;
+        self.back_button
+            .display(canvas, texture_creator, events, font)?;
+
+        Ok(())
+    }
+}
@@ -1,84 +1,26 @@
+use crate::character::Character;
 use crate::events::Events;
+use crate::item::{knockout_kart, Items};
 use crate::level::camera_consts::{
     DEFAULT_CAM_FAR, DEFAULT_CAM_FOLLOW_DIST, DEFAULT_CAM_FOV, DEFAULT_CAM_NEAR,
 };
-use crate::level::{create_enemies, create_powerups, Camera, Level, CHECKPOINTS};
+use crate::level::{Camera, Level};
+use crate::race::{self, RaceRules, RacerResult};
 use crate::sprite::SPRITE_SIZE;
 use crate::sprite::{
-    dist_between, enemy::Enemy, enemy::Fireball, kart::Kart, kart::PowerupType, powerup::Powerup,
-    xz_diff_norm, Sprite, SpriteType,
+    enemy::Enemy, kart::Kart, kart::KartInput, particle::Particle, Sprite, SpriteType,
 };
 use sdl2::keyboard::Keycode;
 
 mod twoplayer_display;
 
-const POWERUP_SPAWN_DIST: f64 = 0.3;
-const START_TIMER: f64 = 3.0;
-const LAPS_TO_WIN: u32 = 4;
+//How long to wait after the race is won before showing the results
+const RESULTS_DELAY: f64 = 3.0;
 
-fn knockout_kart(kart: &mut Kart, spr: &Sprite, dist: f64, time: f64) -> bool {
-    if dist_between(spr, &kart.sprite) < dist {
-        if !kart.knocked_out() {
-            kart.knock_out = time;
-        }
-        return true;
-    }
-
-    false
-}
-
-fn pickup_powerup(powerup: &mut Powerup, kart: &mut Kart, dist: f64) {
-    if dist_between(&powerup.sprite, &kart.sprite) < dist
-        && powerup.can_pickup()
-        && kart.powerup_amt == 0
-    {
-        powerup.sprite.width = 0.0;
-        powerup.sprite.height = 0.0;
-        kart.pickup_powerup();
-    }
-}
-
-fn kart_at_checkpoint(level: &Level, kart: &mut Kart, checkpoint: &mut Sprite) {
-    if level.kart_at_checkpoint(kart, kart.current_checkpoint, 1.0) {
-        if kart.current_checkpoint == level.checkpoints.len() - 1 {
-            kart.laps += 1;
-        }
-
-        kart.current_checkpoint += 1;
-        kart.current_checkpoint %= level.checkpoints.len();
-        checkpoint.trans_x = level.checkpoints[kart.current_checkpoint].0;
-        checkpoint.trans_z = level.checkpoints[kart.current_checkpoint].1;
-    }
-}
-
-fn player_use_powerup(
-    kart: &mut Kart,
-    other_kart: &mut Kart,
-    bananas: &mut Vec<Sprite>,
-    fireballs: &mut Vec<Fireball>,
-) {
-    match kart.use_powerup() {
-        PowerupType::SpeedBoost => {
-            kart.speed += 1.0;
-        }
-        PowerupType::Banana => {
-            let banana = Sprite::new(
-                kart.sprite.trans_x - POWERUP_SPAWN_DIST * kart.sprite.rotation.sin(),
-                kart.sprite.trans_z - POWERUP_SPAWN_DIST * kart.sprite.rotation.cos(),
-                SpriteType::Banana,
-            )
-            .set_size(0.06, 0.06);
-            bananas.push(banana);
-        }
-        PowerupType::Fireball => {
-            let (xdiff, ydiff) = xz_diff_norm(&other_kart.sprite, &kart.sprite);
-            fireballs.push(Fireball::new(
-                kart.sprite.trans_x + POWERUP_SPAWN_DIST * xdiff,
-                kart.sprite.trans_z + POWERUP_SPAWN_DIST * ydiff,
-                other_kart.sprite.sprite_type,
-            ));
-        }
-        _ => {}
+fn kart_at_checkpoint(level: &Level, kart: &mut Kart, checkpoint: &mut Sprite, time: f64) {
+    if kart.update_checkpoint(level, time) {
+        (checkpoint.trans_x, checkpoint.trans_z) =
+            level.checkpoint_position(kart.current_checkpoint);
     }
 }
 
@@ -87,21 +29,32 @@ pub struct TwoplayerState {
     player_kart2: Kart,
     cam1: Camera,
     cam2: Camera,
-    powerups: Vec<Powerup>,
+    prev_cams: [Camera; 2], //Cameras at the start of the last tick
+    interpolation: f64,     //How far between the last two ticks to draw everything
+    items: Items,
     enemies: Vec<Enemy>,
-    bananas: Vec<Sprite>,
-    fireballs: Vec<Fireball>,
+    particles: Vec<Particle>,
     checkpoint1: Sprite,
     checkpoint2: Sprite,
     start_timer: f64,
+    timer: f64,
+    finish_timer: f64, //Time since the race was won
+    rules: RaceRules,
+    swapped: bool,         //Player 2 is shown on top, only happens in a replay
+    camera_switched: bool, //Redraws the level after swapping the views
 }
 
 impl TwoplayerState {
-    pub fn init() -> Self {
-        let mut kart1 = Kart::new(9.0, 35.5, SpriteType::Kart1);
-        let mut kart2 = Kart::new(9.0, 35.1, SpriteType::Kart2);
-        kart1.move_kart(0.0);
-        kart2.move_kart(0.0);
+    //Player 1 drives the first racer and player 2 drives the second
+    pub fn init(level: &Level, rules: RaceRules, racers: &[&Character]) -> Self {
+        let mut kart1 = level
+            .create_kart(0, SpriteType::Kart1)
+            .set_stats(racers[0].stats);
+        let mut kart2 = level
+            .create_kart(1, SpriteType::Kart2)
+            .set_stats(racers[1].stats);
+        kart1.move_kart(0.0, level);
+        kart2.move_kart(0.0, level);
 
         let c1 = Camera::create_following(
             &kart1.sprite,
@@ -119,159 +72,242 @@ impl TwoplayerState {
             DEFAULT_CAM_FOV,
         );
 
-        Self {
+        let mut state = Self {
             player_kart1: kart1,
             player_kart2: kart2,
             cam1: c1,
             cam2: c2,
-            powerups: create_powerups(),
-            enemies: create_enemies(),
-            bananas: vec![],
-            fireballs: vec![],
-            checkpoint1: Sprite::from_tuple(CHECKPOINTS[0], SpriteType::Checkpoint1)
+            prev_cams: [c1, c2],
+            interpolation: 1.0,
+            items: Items::new(level, rules),
+            enemies: if rules.enemies {
+                level.create_enemies()
+            } else {
+                vec![]
+            },
+            particles: vec![],
+            checkpoint1: Sprite::from_tuple(level.checkpoint_position(0), SpriteType::Checkpoint1)
                 .set_size(SPRITE_SIZE, SPRITE_SIZE),
-            checkpoint2: Sprite::from_tuple(CHECKPOINTS[0], SpriteType::Checkpoint2)
+            checkpoint2: Sprite::from_tuple(level.checkpoint_position(0), SpriteType::Checkpoint2)
                 .set_size(SPRITE_SIZE, SPRITE_SIZE),
-            start_timer: START_TIMER,
-        }
+            start_timer: rules.countdown,
+            timer: 0.0,
+            finish_timer: 0.0,
+            rules,
+            swapped: false,
+            camera_switched: false,
+        };
+        state.save_positions();
+        state
     }
 
-    pub fn use_powerups(&mut self, events: &mut Events) {
-        if events.key_is_pressed_once(Keycode::Down) {
-            player_use_powerup(
-                &mut self.player_kart1,
-                &mut self.player_kart2,
-                &mut self.bananas,
-                &mut self.fireballs,
-            );
-        }
+    //Both players' controls for one tick
+    pub fn read_inputs(events: &mut Events) -> [KartInput; 2] {
+        [
+            KartInput::from_keys(
+                events,
+                Keycode::Up,
+                Keycode::RShift,
+                Keycode::RCtrl,
+                Keycode::Left,
+                Keycode::Right,
+                Keycode::Down,
+            ),
+            KartInput::from_keys(
+                events,
+                Keycode::W,
+                Keycode::LShift,
+                Keycode::LCtrl,
+                Keycode::A,
+                Keycode::D,
+                Keycode::S,
+            ),
+        ]
+    }
+
+    fn use_powerups(&mut self, inputs: &[KartInput; 2]) {
+        let mut karts = [&mut self.player_kart1, &mut self.player_kart2];
 
-        if events.key_is_pressed_once(Keycode::S) {
-            player_use_powerup(
-                &mut self.player_kart2,
-                &mut self.player_kart1,
-                &mut self.bananas,
-                &mut self.fireballs,
-            );
+        for (user, input) in inputs.iter().enumerate() {
+            if input.use_item {
+                self.items.use_powerup(&mut karts, user);
+            }
         }
     }
 
     fn update_enemies(&mut self, dt: f64) {
         for enemy in &mut self.enemies {
-            knockout_kart(&mut self.player_kart1, &enemy.sprite, 0.2, 1.0);
-            knockout_kart(&mut self.player_kart2, &enemy.sprite, 0.2, 1.0);
+            if !enemy.stunned() {
+                knockout_kart(&mut self.player_kart1, &enemy.sprite, 0.2, 1.0);
+                knockout_kart(&mut self.player_kart2, &enemy.sprite, 0.2, 1.0);
+            }
             enemy.update(dt);
         }
     }
 
-    fn update_fireballs(&mut self, dt: f64) {
-        //Update the fireballs
-        let mut index = 0;
-        while index < self.fireballs.len() {
-            let fireball = &mut self.fireballs[index];
-
-            match fireball.target {
-                SpriteType::Kart1 => fireball.update(dt, &self.player_kart1.sprite),
-                SpriteType::Kart2 => fireball.update(dt, &self.player_kart2.sprite),
-                _ => {}
-            }
-
-            index += 1;
-
-            if knockout_kart(&mut self.player_kart1, &fireball.sprite, 0.1, 2.0) {
-                self.fireballs.remove(index - 1);
-                index -= 1;
-                continue;
-            }
-
-            if knockout_kart(&mut self.player_kart2, &fireball.sprite, 0.1, 2.0) {
-                self.fireballs.remove(index - 1);
-                index -= 1;
-                continue;
-            }
-
-            if fireball.timer > 8.0 {
-                self.fireballs.remove(index - 1);
-                index -= 1;
-                continue;
-            }
-        }
+    fn update_items(&mut self, level: &Level, dt: f64) {
+        self.items.update(
+            &mut [&mut self.player_kart1, &mut self.player_kart2],
+            &mut self.enemies,
+            level,
+            dt,
+        );
     }
 
-    fn update_bananas(&mut self) {
-        //Check for player collision with bananas
-        let mut index = 0;
-        while index < self.bananas.len() {
-            let banana = &self.bananas[index];
-            index += 1;
-
-            if knockout_kart(&mut self.player_kart1, banana, 0.1, 3.0) {
-                self.bananas.remove(index - 1);
-                index -= 1;
-                continue;
-            }
+    fn update_particles(&mut self, dt: f64) {
+        if let Some(particle) = self.player_kart1.spawn_particle(dt) {
+            self.particles.push(particle);
+        }
 
-            if knockout_kart(&mut self.player_kart2, banana, 0.1, 3.0) {
-                self.bananas.remove(index - 1);
-                index -= 1;
-                continue;
-            }
+        if let Some(particle) = self.player_kart2.spawn_particle(dt) {
+            self.particles.push(particle);
         }
-    }
 
-    fn update_powerups(&mut self, dt: f64) {
-        for powerup in &mut self.powerups {
-            pickup_powerup(powerup, &mut self.player_kart1, 0.2);
-            pickup_powerup(powerup, &mut self.player_kart2, 0.2);
-            powerup.update(dt);
+        for particle in &mut self.particles {
+            particle.update(dt);
         }
+        self.particles.retain(|particle| !particle.done());
     }
 
     fn race_over(&self) -> bool {
-        self.player_kart1.laps == 4 || self.player_kart2.laps == 4
+        self.player_kart1.laps == self.rules.laps || self.player_kart2.laps == self.rules.laps
     }
 
-    fn update_karts(&mut self, level: &Level, events: &Events, dt: f64) {
+    fn update_karts(&mut self, level: &Level, inputs: &[KartInput; 2], dt: f64) {
         //Move the karts
         if self.start_timer <= 0.0 && !self.race_over() {
-            self.player_kart1
-                .drive_kart(events, Keycode::Up, Keycode::Left, Keycode::Right);
-            self.player_kart2
-                .drive_kart(events, Keycode::W, Keycode::A, Keycode::D);
-            self.player_kart1.move_kart(dt);
-            self.player_kart2.move_kart(dt);
+            self.player_kart1.drive_kart(&inputs[0]);
+            self.player_kart2.drive_kart(&inputs[1]);
+            self.player_kart1.move_kart(dt, level);
+            self.player_kart2.move_kart(dt, level);
+            self.player_kart1.bump(&mut self.player_kart2, level);
         }
 
-        kart_at_checkpoint(level, &mut self.player_kart1, &mut self.checkpoint1);
-        kart_at_checkpoint(level, &mut self.player_kart2, &mut self.checkpoint2);
+        kart_at_checkpoint(
+            level,
+            &mut self.player_kart1,
+            &mut self.checkpoint1,
+            self.timer,
+        );
+        kart_at_checkpoint(
+            level,
+            &mut self.player_kart2,
+            &mut self.checkpoint2,
+            self.timer,
+        );
+        self.player_kart1.update_wrong_way(level, dt);
+        self.player_kart2.update_wrong_way(level, dt);
 
         self.player_kart1.apply_friction(level);
         self.player_kart2.apply_friction(level);
 
-        if !self.player_kart1.knocked_out() {
-            self.cam1.follow(&self.player_kart1.sprite, 1.1);
+        self.update_cameras();
+
+        race::update_positions(
+            &mut [&mut self.player_kart1, &mut self.player_kart2],
+            level,
+            self.rules.laps,
+        );
+
+        if self.race_over() {
+            self.player_kart1.stop();
+            self.player_kart2.stop();
         }
-        if !self.player_kart2.knocked_out() {
-            self.cam2.follow(&self.player_kart2.sprite, 1.1);
+    }
+
+    //The kart shown in the top (0) or bottom (1) view
+    fn view_kart(&self, view: usize) -> &Kart {
+        if (view == 0) != self.swapped {
+            &self.player_kart1
+        } else {
+            &self.player_kart2
         }
+    }
 
-        if self.race_over() {
-            self.player_kart1.speed = 0.0;
-            self.player_kart1.sprite.rotation_speed = 0.0;
-            self.player_kart2.speed = 0.0;
-            self.player_kart2.sprite.rotation_speed = 0.0;
+    fn update_cameras(&mut self) {
+        let (top, bottom) = if self.swapped {
+            (&self.player_kart2, &self.player_kart1)
+        } else {
+            (&self.player_kart1, &self.player_kart2)
+        };
+
+        if !top.knocked_out() {
+            self.cam1.follow(&top.sprite, 1.1);
+        }
+        if !bottom.knocked_out() {
+            self.cam2.follow(&bottom.sprite, 1.1);
         }
     }
 
-    pub fn update(&mut self, level: &Level, events: &Events, dt: f64) {
-        self.update_karts(level, events, dt);
+    pub fn kart_count(&self) -> usize {
+        2
+    }
+
+    //Shows the kart on top of the screen, the other player's kart is shown below
+    pub fn watch_kart(&mut self, index: usize) {
+        let swapped = index % self.kart_count() == 1;
+        if swapped != self.swapped {
+            self.swapped = swapped;
+            std::mem::swap(&mut self.cam1, &mut self.cam2);
+            self.prev_cams.swap(0, 1);
+            self.camera_switched = true;
+        }
+    }
+
+    //Remembers where everything is at the start of a tick
+    //so that drawing can blend between ticks
+    fn save_positions(&mut self) {
+        self.prev_cams = [self.cam1, self.cam2];
+        self.player_kart1.sprite.save_position();
+        self.player_kart2.sprite.save_position();
+        self.checkpoint1.save_position();
+        self.checkpoint2.save_position();
+        for enemy in &mut self.enemies {
+            enemy.sprite.save_position();
+        }
+        for particle in &mut self.particles {
+            particle.sprite.save_position();
+        }
+        self.items.save_positions();
+    }
+
+    //Sets how far (0 to 1) between the last two ticks everything is drawn
+    pub fn set_interpolation(&mut self, t: f64) {
+        self.interpolation = t;
+    }
+
+    //Runs one tick of the race
+    pub fn update(&mut self, level: &Level, inputs: &[KartInput; 2], dt: f64) {
+        self.save_positions();
+        self.use_powerups(inputs);
+        self.update_karts(level, inputs, dt);
         self.update_enemies(dt);
-        self.update_bananas();
-        self.update_powerups(dt);
-        self.update_fireballs(dt);
+        self.update_items(level, dt);
+        self.update_particles(dt);
 
         if self.start_timer > -1.0 {
             self.start_timer -= dt;
         }
+
+        if self.start_timer < 0.0 && !self.race_over() {
+            self.timer += dt;
+        }
+
+        if self.race_over() {
+            self.finish_timer += dt;
+        }
+    }
+
+    //Returns true once the players have had a few seconds to see who won
+    pub fn results_ready(&self) -> bool {
+        self.finish_timer > RESULTS_DELAY
+    }
+
+    //How both players did in the race
+    pub fn results(&self) -> Vec<RacerResult> {
+        vec![
+            RacerResult::new("Player 1", &self.player_kart1, self.rules.laps),
+            RacerResult::new("Player 2", &self.player_kart2, self.rules.laps),
+        ]
     }
 }
@@ -1,7 +1,7 @@
-use super::{TwoplayerState, LAPS_TO_WIN, START_TIMER};
-use crate::level::Level;
+use super::TwoplayerState;
+use crate::level::{Camera, Level};
 use crate::menu::Text;
-use crate::sprite::{bitmap::BitMap, kart::PowerupType, Sprite, SpriteType};
+use crate::sprite::{Sprite, SpriteType};
 use crate::{display, HEIGHT, WIDTH};
 use sdl2::pixels::Color;
 use sdl2::rect::{Point, Rect};
@@ -25,11 +25,11 @@ impl TwoplayerState {
             Color::BLACK,
             32,
         );
-        if self.player_kart1.laps >= LAPS_TO_WIN {
+        if self.player_kart1.laps >= self.rules.laps {
             victory_text.text = String::from("PLAYER 1 WINS!");
             victory_text.color = Color::RED;
             victory_text.display_center(canvas, texture_creator, font)?;
-        } else if self.player_kart2.laps >= LAPS_TO_WIN {
+        } else if self.player_kart2.laps >= self.rules.laps {
             victory_text.text = String::from("PLAYER 2 WINS!");
             victory_text.color = Color::BLUE;
             victory_text.display_center(canvas, texture_creator, font)?;
@@ -38,35 +38,38 @@ impl TwoplayerState {
         Ok(())
     }
 
-    pub fn create_background_texture(
-        &self,
-        pixel_buffer: &mut [u8],
-        level: &Level,
-        track_textures: &HashMap<u32, BitMap>,
-    ) {
+    //A player's camera part of the way between the last two ticks
+    fn view_cam(&self, player: usize) -> Camera {
+        let cam = if player == 0 { &self.cam1 } else { &self.cam2 };
+        cam.interpolated(&self.prev_cams[player], self.interpolation)
+    }
+
+    fn create_background_texture(&mut self, pixel_buffer: &mut [u8], level: &Level) {
         let sz = pixel_buffer.len() / 2;
-        if self.player_kart1.moving() || self.start_timer == START_TIMER {
+        //Always drawn in the first second of the countdown since the
+        //race can tick a few times before the first frame is shown
+        let starting = self.start_timer > self.rules.countdown - 1.0 || self.camera_switched;
+        if self.view_kart(0).moving() || starting {
             level.display_level(
                 &mut pixel_buffer[..sz],
                 WIDTH,
                 HEIGHT / 2,
-                &self.cam1,
-                track_textures,
+                &self.view_cam(0),
             );
         }
 
-        if self.player_kart2.moving() || self.start_timer == START_TIMER {
+        if self.view_kart(1).moving() || starting {
             level.display_level(
                 &mut pixel_buffer[sz..],
                 WIDTH,
                 HEIGHT / 2,
-                &self.cam2,
-                track_textures,
+                &self.view_cam(1),
             );
         }
+        self.camera_switched = false;
     }
 
-    pub fn display_background(
+    fn display_background(
         &self,
         canvas: &mut Canvas<Window>,
         pixel_buffer: &[u8],
@@ -94,12 +97,40 @@ impl TwoplayerState {
         Ok(())
     }
 
+    //Draws the track and sprites of both views, player 1 (or the kart
+    //being watched in a replay) on top and player 2 below
+    pub fn display_views(
+        &mut self,
+        canvas: &mut Canvas<Window>,
+        level: &Level,
+        pixel_buffer: &mut [u8],
+        background_texture: &mut Texture,
+        sprite_assets: &mut HashMap<SpriteType, Texture>,
+    ) -> Result<(), String> {
+        let sz = pixel_buffer.len() / 2;
+        let canvas_texture_rect =
+            display::calculate_texture_rect(canvas.output_size()?, WIDTH, HEIGHT);
+
+        self.create_background_texture(pixel_buffer, level);
+        self.display_background(canvas, &pixel_buffer[..sz], background_texture, 0)?;
+        self.display_sprites(canvas, sprite_assets, SpriteType::Kart1)?;
+        self.display_background(
+            canvas,
+            &pixel_buffer[sz..],
+            background_texture,
+            canvas_texture_rect.height() / 2,
+        )?;
+        self.display_sprites(canvas, sprite_assets, SpriteType::Kart2)?;
+
+        Ok(())
+    }
+
     pub fn display_hud(
         &self,
         canvas: &mut Canvas<Window>,
         texture_creator: &TextureCreator<WindowContext>,
         font: &Font,
-        powerup_icons: &HashMap<PowerupType, Texture>,
+        powerup_icons: &[Texture],
     ) -> Result<(), String> {
         let canvas_dimensions = canvas.output_size()?;
         let canvas_dimensions_half = (canvas_dimensions.0, canvas_dimensions.1 / 2);
@@ -112,13 +143,21 @@ impl TwoplayerState {
             self.start_timer,
         )?;
 
-        display::display_player_info(canvas, texture_creator, font, &self.player_kart1, 0, 0)?;
+        display::display_player_info(canvas, texture_creator, font, self.view_kart(0), 0, 0)?;
+        display::display_lap_times(
+            canvas,
+            texture_creator,
+            font,
+            self.view_kart(0),
+            self.timer,
+            0,
+        )?;
 
         display::display_powerup_icons(
             canvas,
             powerup_icons,
             48,
-            &self.player_kart1,
+            self.view_kart(0),
             (canvas_dimensions.0 / 2) as i32,
             16,
         )?;
@@ -127,16 +166,24 @@ impl TwoplayerState {
             canvas,
             texture_creator,
             font,
-            &self.player_kart2,
+            self.view_kart(1),
             0,
             canvas_dimensions_half.1 as i32,
         )?;
+        display::display_lap_times(
+            canvas,
+            texture_creator,
+            font,
+            self.view_kart(1),
+            self.timer,
+            canvas_dimensions_half.1 as i32,
+        )?;
 
         display::display_powerup_icons(
             canvas,
             powerup_icons,
             48,
-            &self.player_kart2,
+            self.view_kart(1),
             (canvas_dimensions.0 / 2) as i32,
             canvas_dimensions_half.1 as i32 + 16,
         )?;
@@ -146,55 +193,61 @@ impl TwoplayerState {
         Ok(())
     }
 
-    fn get_sprites_to_draw(&self, kart: SpriteType) -> Vec<&Sprite> {
+    //Copies of the sprites placed between the last two ticks
+    fn get_sprites_to_draw(&self, kart: SpriteType) -> Vec<Sprite> {
         let mut sprites_to_draw = vec![];
 
         sprites_to_draw.push(&self.player_kart1.sprite);
         sprites_to_draw.push(&self.player_kart2.sprite);
 
+        //Each view shows the checkpoint of the kart it follows
+        let (top, bottom) = if self.swapped {
+            (&self.checkpoint2, &self.checkpoint1)
+        } else {
+            (&self.checkpoint1, &self.checkpoint2)
+        };
         match kart {
-            SpriteType::Kart1 => sprites_to_draw.push(&self.checkpoint1),
-            SpriteType::Kart2 => sprites_to_draw.push(&self.checkpoint2),
+            SpriteType::Kart1 => sprites_to_draw.push(top),
+            SpriteType::Kart2 => sprites_to_draw.push(bottom),
             _ => {}
         }
 
-        for powerup in &self.powerups {
-            sprites_to_draw.push(&powerup.sprite);
-        }
+        sprites_to_draw.extend(self.items.sprites());
 
         for enemy in &self.enemies {
             sprites_to_draw.push(&enemy.sprite);
         }
 
-        for fireball in &self.fireballs {
-            sprites_to_draw.push(&fireball.sprite);
+        for particle in &self.particles {
+            sprites_to_draw.push(&particle.sprite);
         }
 
-        for banana in &self.bananas {
-            sprites_to_draw.push(banana);
-        }
+        let mut sprites_to_draw: Vec<Sprite> = sprites_to_draw
+            .iter()
+            .map(|sprite| sprite.interpolated(self.interpolation))
+            .collect();
 
         let cam = match kart {
-            SpriteType::Kart1 => &self.cam1,
-            SpriteType::Kart2 => &self.cam2,
+            SpriteType::Kart1 => self.view_cam(0),
+            SpriteType::Kart2 => self.view_cam(1),
             _ => return sprites_to_draw, //Returns sprites out of order
         };
 
         //Sort sprite vector based on distance to camera
         sprites_to_draw.sort_by(|sprite1, sprite2| {
             sprite2
-                .dist2_to_camera(cam)
-                .partial_cmp(&sprite1.dist2_to_camera(cam))
+                .dist2_to_camera(&cam)
+                .partial_cmp(&sprite1.dist2_to_camera(&cam))
                 .unwrap()
         });
 
         sprites_to_draw
     }
 
-    pub fn display_sprites(
+    fn display_sprites(
         &mut self,
         canvas: &mut Canvas<Window>,
-        sprite_assets: &HashMap<SpriteType, Texture>,
+        sprite_assets: &mut HashMap<SpriteType, Texture>,
         kart: SpriteType,
     ) -> Result<(), String> {
         let canvas_dimensions = canvas.output_size()?;
@@ -210,17 +263,17 @@ impl TwoplayerState {
             canvas_texture_rect.height() / 2,
         );
 
-        match kart {
-            SpriteType::Kart1 => {
-                self.player_kart1.sprite.camera_kart = !self.player_kart1.knocked_out();
-            }
-            SpriteType::Kart2 => {
-                self.player_kart2.sprite.camera_kart = !self.player_kart2.knocked_out();
-            }
-            _ => {}
+        let followed = match (kart, self.swapped) {
+            (SpriteType::Kart1, false) | (SpriteType::Kart2, true) => Some(&mut self.player_kart1),
+            (SpriteType::Kart2, false) | (SpriteType::Kart1, true) => Some(&mut self.player_kart2),
+            _ => None,
+        };
+        if let Some(followed) = followed {
+            followed.sprite.camera_kart = !followed.knocked_out();
         }
 
-        let sprites_to_draw = self.get_sprites_to_draw(kart);
+        let sprites = self.get_sprites_to_draw(kart);
+        let sprites_to_draw: Vec<&Sprite> = sprites.iter().collect();
         match kart {
             SpriteType::Kart1 => {
                 let offset_y = if display::cmp_aspect(canvas_dimensions_half, WIDTH, HEIGHT / 2) {
@@ -231,7 +284,7 @@ impl TwoplayerState {
 
                 display::display_sprites(
                     canvas,
-                    &self.cam1,
+                    &self.view_cam(0),
                     &sprites_to_draw,
                     canvas_dimensions_half,
                     (0, offset_y),
@@ -249,7 +302,7 @@ impl TwoplayerState {
 
                 display::display_sprites(
                     canvas,
-                    &self.cam2,
+                    &self.view_cam(1),
                     &sprites_to_draw,
                     canvas_dimensions_half,
                     (0, origin_y + texture_rect.y() / 2 + offset_y),